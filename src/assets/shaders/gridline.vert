@@ -0,0 +1,9 @@
+#version 330 core
+
+layout (location = 0) in vec3 Position;
+// center in xy, scale in zw: screen = (position - center) * scale
+uniform vec4 camera;
+
+void main() {
+    gl_Position = vec4((Position.xy - camera.xy) * camera.zw, Position.z, 1.0);
+}
\ No newline at end of file
@@ -0,0 +1,10 @@
+#version 330 core
+
+uniform vec4 inColor;
+in float alpha;
+
+out vec4 Color;
+
+void main() {
+    Color = vec4(inColor.rgb, inColor.a * alpha);
+}
\ No newline at end of file
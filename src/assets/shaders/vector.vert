@@ -1,13 +1,15 @@
 #version 330 core
 
 layout (location = 0) in vec3 Position;
+// center in xy, scale in zw: screen = (position - center) * scale
+uniform vec4 camera;
 layout (location = 1) in float in_magnitude;
 uniform float in_range;
 
 out float magnitude;
 out float range;
 void main() {
-    gl_Position = vec4(Position, 1.0);
+    gl_Position = vec4((Position.xy - camera.xy) * camera.zw, Position.z, 1.0);
     magnitude = in_magnitude;
     range = in_range;
 }
\ No newline at end of file
@@ -0,0 +1,385 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::process;
+use std::rc::Rc;
+use std::time::Instant;
+
+use sdl_physics::simulator::expression::FieldExpression;
+use sdl_physics::simulator::trajectory::{TrajectoryFormat, TrajectoryRecorder};
+use sdl_physics::simulator::{
+    integrator, ForceSource, SimulateFunction, Simulator, FUNCTION_NAMES,
+};
+
+const USAGE: &str = "usage: sdl_physics-run --steps N [options]
+
+options:
+    --scene FILE         start from a saved scene (default: the 40x40 grid)
+    --function NAME[:W]  add a force function with weight W (default 1), may be
+                         repeated for different functions; replaces the scene's forces
+    --expression TEXT    add an acceleration field such as \"ax = -k*x; ay = -k*y; k = 10\",
+                         at most once
+    --param NAME=VALUE   set a force constant such as Harmonic.k or Harmonic.weight,
+                         the timestep or the restitution, may be repeated
+    --integrator NAME    integrator, overrides the scene's
+    --timestep DT        timestep, overrides the scene's (default 1/120)
+    --seed N             seed for the emitters, overrides the scene's (default 0)
+    --steps N            number of steps to run
+    --output FILE        trajectory output, .csv for text, anything else binary
+    --every N            record a frame every N steps and at the end (default 1)
+    --diagnostics FILE   write energy, momentum and angular momentum as CSV every N steps
+    --save-scene FILE    write the final state as a scene";
+
+struct Options {
+    scene: Option<String>,
+    forces: Vec<ForceSource>,
+    integrator: Option<String>,
+    parameters: Vec<(String, f32)>,
+    timestep: Option<f32>,
+    seed: Option<u64>,
+    steps: usize,
+    output: Option<String>,
+    every: usize,
+    diagnostics: Option<String>,
+    save_scene: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        scene: None,
+        forces: Vec::new(),
+        integrator: None,
+        parameters: Vec::new(),
+        timestep: None,
+        seed: None,
+        steps: 0,
+        output: None,
+        every: 1,
+        diagnostics: None,
+        save_scene: None,
+    };
+    let mut steps = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "--scene" => options.scene = Some(value()?),
+            "--function" => {
+                let text = value()?;
+                let (name, weight) = match text.split_once(':') {
+                    Some((name, weight)) => (
+                        name,
+                        weight
+                            .parse::<f32>()
+                            .map_err(|e| format!("--function {}: {}", name, e))?,
+                    ),
+                    None => (text.as_str(), 1.0),
+                };
+                let function = SimulateFunction::from_name(name).ok_or_else(|| {
+                    format!(
+                        "unknown function \"{}\", expected one of {}",
+                        name,
+                        FUNCTION_NAMES.join(", ")
+                    )
+                })?;
+                let mut source = ForceSource::new(function);
+                source.weight = weight;
+                add_force(&mut options.forces, source)?;
+            }
+            "--expression" => {
+                let text = value()?;
+                let expression =
+                    FieldExpression::parse(&text).map_err(|e| format!("--expression: {}", e))?;
+                add_force(
+                    &mut options.forces,
+                    ForceSource::new(SimulateFunction::Expression(expression)),
+                )?;
+            }
+            "--param" => {
+                let text = value()?;
+                let (name, v) = text
+                    .split_once('=')
+                    .ok_or_else(|| format!("--param: expected NAME=VALUE, got \"{}\"", text))?;
+                let v = v
+                    .trim()
+                    .parse::<f32>()
+                    .map_err(|e| format!("--param {}: {}", name, e))?;
+                options.parameters.push((name.trim().to_string(), v));
+            }
+            "--integrator" => {
+                let name = value()?;
+                if integrator::integrator_from_name(&name).is_none() {
+                    return Err(format!(
+                        "unknown integrator \"{}\", expected one of {}",
+                        name,
+                        integrator::INTEGRATOR_NAMES.join(", ")
+                    ));
+                }
+                options.integrator = Some(name);
+            }
+            "--timestep" => {
+                let dt = value()?
+                    .parse::<f32>()
+                    .map_err(|e| format!("--timestep: {}", e))?;
+                if dt <= 0.0 {
+                    return Err(String::from("--timestep must be positive"));
+                }
+                options.timestep = Some(dt);
+            }
+            "--seed" => {
+                options.seed = Some(
+                    value()?
+                        .parse::<u64>()
+                        .map_err(|e| format!("--seed: {}", e))?,
+                )
+            }
+            "--steps" => {
+                steps = Some(
+                    value()?
+                        .parse::<usize>()
+                        .map_err(|e| format!("--steps: {}", e))?,
+                )
+            }
+            "--output" => options.output = Some(value()?),
+            "--every" => {
+                options.every = value()?
+                    .parse::<usize>()
+                    .map_err(|e| format!("--every: {}", e))?
+                    .max(1)
+            }
+            "--diagnostics" => options.diagnostics = Some(value()?),
+            "--save-scene" => options.save_scene = Some(value()?),
+            "--help" | "-h" => return Err(String::new()),
+            other => return Err(format!("unknown argument \"{}\"", other)),
+        }
+    }
+    options.steps = steps.ok_or_else(|| String::from("--steps is required"))?;
+    Ok(options)
+}
+
+/* the simulator keeps one source per function, so a repeat would silently replace the
+earlier one */
+fn add_force(forces: &mut Vec<ForceSource>, source: ForceSource) -> Result<(), String> {
+    if forces.iter().any(|f| f.name() == source.name()) {
+        return Err(match source.function {
+            SimulateFunction::Expression(_) => String::from(
+                "--expression given more than once, combine the fields into one expression",
+            ),
+            _ => format!("--function {} given more than once", source.name()),
+        });
+    }
+    forces.push(source);
+    Ok(())
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let mut simulator = Simulator::new(1.0 / 120.0);
+    match &options.scene {
+        Some(path) => simulator.load_scene(path)?,
+        None => {
+            for i in -20..20 {
+                for j in -20..20 {
+                    simulator.add_particle(i as f32, j as f32, 0.0, 0.0, 1.0, 1.0)?;
+                }
+            }
+        }
+    }
+    if !options.forces.is_empty() {
+        simulator.set_forces(options.forces);
+    }
+    if let Some(name) = &options.integrator {
+        simulator.set_integrator(integrator::integrator_from_name(name).unwrap());
+    }
+    if let Some(dt) = options.timestep {
+        simulator.set_timestep(dt);
+    }
+    if let Some(seed) = options.seed {
+        simulator.set_seed(seed);
+    }
+    for (name, value) in &options.parameters {
+        simulator.set_parameter(name, *value)?;
+    }
+    let sim = Rc::new(RefCell::new(simulator));
+
+    let mut recorder = match &options.output {
+        Some(path) => Some(TrajectoryRecorder::create(
+            path,
+            TrajectoryFormat::from_path(path),
+            options.every,
+        )?),
+        None => None,
+    };
+    let mut diagnostics = match &options.diagnostics {
+        Some(path) => {
+            let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+            let mut out = BufWriter::new(file);
+            writeln!(
+                out,
+                "time,kinetic,potential,total,px,py,angular_momentum,drift"
+            )
+            .map_err(|e| format!("{}: {}", path, e))?;
+            Some((path, out))
+        }
+        None => None,
+    };
+
+    let start = Instant::now();
+    let start_step = sim.borrow().steps();
+    for i in 0..=options.steps {
+        // both outputs share the rows they cover, including the final state
+        let due = i % options.every == 0 || i == options.steps;
+        if let Some(r) = recorder.as_mut().filter(|_| due) {
+            r.write_frame(&sim.borrow())?;
+        }
+        if let Some((path, out)) = diagnostics.as_mut().filter(|_| due) {
+            write_diagnostics(out, &sim.borrow()).map_err(|e| format!("{}: {}", path, e))?;
+        }
+        if i < options.steps {
+            Simulator::step(sim.clone(), 1);
+        }
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    if let Some((path, mut out)) = diagnostics {
+        out.flush().map_err(|e| format!("{}: {}", path, e))?;
+    }
+
+    let frames = recorder.as_ref().map(|r| r.frames_written());
+    if let Some(r) = recorder {
+        r.finish()?;
+    }
+    if let Some(path) = &options.save_scene {
+        sim.borrow().save_scene(path)?;
+    }
+
+    print_summary(&sim.borrow(), sim.borrow().steps() - start_step, elapsed, frames);
+    Ok(())
+}
+
+fn write_diagnostics(out: &mut impl Write, sim: &Simulator) -> std::io::Result<()> {
+    let d = sim.diagnostics();
+    let optional = |v: Option<f32>| v.map(|v| v.to_string()).unwrap_or_default();
+    writeln!(
+        out,
+        "{},{},{},{},{},{},{},{}",
+        d.time,
+        d.kinetic_energy,
+        optional(d.potential_energy),
+        optional(d.total_energy()),
+        d.momentum.x,
+        d.momentum.y,
+        d.angular_momentum,
+        optional(d.energy_drift)
+    )
+}
+
+fn print_summary(sim: &Simulator, steps: usize, elapsed: f64, frames: Option<usize>) {
+    let particles = sim.particle_list.borrow();
+    let count = particles.len();
+    let mut total_mass = 0.0;
+    let mut center = (0.0, 0.0);
+    let mut speed_sum = 0.0;
+    let mut max_speed: f32 = 0.0;
+    for p in particles.iter() {
+        let pos = p.get_pos();
+        let vel = p.get_vel();
+        let speed = (vel.x * vel.x + vel.y * vel.y).sqrt();
+        total_mass += p.get_mass();
+        center.0 += pos.x * p.get_mass();
+        center.1 += pos.y * p.get_mass();
+        speed_sum += speed;
+        max_speed = max_speed.max(speed);
+    }
+
+    let enabled: Vec<&ForceSource> = sim.forces().iter().filter(|s| s.enabled).collect();
+    let names: Vec<String> = enabled
+        .iter()
+        .map(|s| {
+            if s.weight == 1.0 {
+                s.name().to_string()
+            } else {
+                format!("{} x{}", s.name(), s.weight)
+            }
+        })
+        .collect();
+    if names.is_empty() {
+        println!("forces:         none");
+    } else {
+        println!("forces:         {}", names.join(", "));
+    }
+    let constants: Vec<String> = enabled
+        .iter()
+        .flat_map(|s| s.parameters().into_iter().skip(1))
+        .map(|p| format!("{}={}", p.name, p.value))
+        .collect();
+    if !constants.is_empty() {
+        println!("parameters:     {}", constants.join(", "));
+    }
+    println!("integrator:     {}", sim.integrator().name());
+    println!("timestep:       {}", sim.timestep());
+    println!("steps:          {}", steps);
+    println!("time:           {:.4}", sim.time());
+    println!("particles:      {}", count);
+    if !sim.emitters().is_empty() || !sim.sinks().is_empty() {
+        let rate: f32 = sim.emitters().iter().map(|e| e.rate).sum();
+        println!("emitters:       {} ({} per second)", sim.emitters().len(), rate);
+        let absorbed: Vec<String> = sim.sinks().iter().map(|s| s.absorbed.to_string()).collect();
+        if absorbed.is_empty() {
+            println!("sinks:          none");
+        } else {
+            println!("sinks absorbed: {}", absorbed.join(", "));
+        }
+    }
+    if count > 0 {
+        println!(
+            "center of mass: ({:.5}, {:.5})",
+            center.0 / total_mass,
+            center.1 / total_mass
+        );
+        println!("mean speed:     {:.5}", speed_sum / count as f32);
+        println!("max speed:      {:.5}", max_speed);
+        let d = sim.diagnostics();
+        println!("kinetic energy: {:.5}", d.kinetic_energy);
+        match d.total_energy() {
+            Some(total) => {
+                println!("potential:      {:.5}", d.potential_energy.unwrap());
+                println!("total energy:   {:.5}", total);
+            }
+            None => println!("potential:      n/a (non-conservative force)"),
+        }
+        if let Some(drift) = d.energy_drift {
+            println!("energy drift:   {:.3e}", drift);
+        }
+        println!("momentum:       ({:.5}, {:.5})", d.momentum.x, d.momentum.y);
+        println!("angular mom.:   {:.5}", d.angular_momentum);
+    }
+    if let Some(frames) = frames {
+        println!("frames written: {}", frames);
+    }
+    println!(
+        "wall time:      {:.3}s ({:.0} steps/s)",
+        elapsed,
+        steps as f64 / elapsed.max(1e-9)
+    );
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("error: {}", e);
+            }
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = run(options) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
@@ -1,239 +1,417 @@
 use crate::gl_render;
-use crate::simulator::{Simulator, Particle};
+use crate::simulator::emitters::{EmitterShape, SinkShape};
+use crate::simulator::sources::FieldSource;
+use crate::simulator::{Particle, Simulator};
 use crate::window;
-use gl;
-use freetype;
 
+use std::collections::HashMap;
 use std::ffi::CString;
 
-impl Simulator {
-    pub fn draw_particles(
-        &self,
-        buf_id: &gl::types::GLuint,
-        vao: &gl::types::GLuint,
-        buf_size: &mut usize,
-        window_info: &window::WindowData,
-    ) -> usize {
-        let mut positions: Vec<f32> = Vec::with_capacity(self.particle_list.borrow().len());
-        for p in self.particle_list.borrow().iter() {
-            let pos = p.get_pos();
-            positions.push(pos.x / window_info.x_range);
-            positions.push(pos.y / window_info.y_range);
-            positions.push(0.0);
+/* points the program's camera uniform at the current view, the program must be in use */
+pub fn set_camera(window_info: &window::WindowData, program: &gl_render::Program) {
+    let transform = window_info.camera_transform();
+    unsafe {
+        let location = window_info.gl.GetUniformLocation(
+            program.id(),
+            (CString::new("camera").unwrap()).as_ptr(),
+        );
+        window_info.gl.Uniform4f(
+            location,
+            transform[0],
+            transform[1],
+            transform[2],
+            transform[3],
+        );
+    }
+}
+
+pub fn draw_particles(
+    sim: &Simulator,
+    buf_id: &gl::types::GLuint,
+    vao: &gl::types::GLuint,
+    buf_size: &mut usize,
+    window_info: &window::WindowData,
+) -> usize {
+    let mut positions: Vec<f32> = Vec::with_capacity(sim.particle_list.borrow().len());
+    for p in sim.particle_list.borrow().iter() {
+        let (a, b) = window_info.view_coordinates(p);
+        positions.push(a);
+        positions.push(b);
+        positions.push(0.0);
+    }
+
+    if positions.len() > *buf_size {
+        unsafe {
+            window_info.gl.BindVertexArray(*vao);
+            window_info.gl.BindBuffer(gl::ARRAY_BUFFER, *buf_id);
+            window_info.gl.VertexAttribPointer(
+                0,
+                positions.len() as gl::types::GLint,
+                gl::FLOAT,
+                gl::FALSE,
+                (3 * std::mem::size_of::<f32>()) as gl::types::GLint,
+                std::ptr::null(),
+            );
+            window_info.gl.BufferData(
+                gl::ARRAY_BUFFER,
+                (positions.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
+                positions.as_ptr() as *const gl::types::GLvoid,
+                gl::DYNAMIC_DRAW,
+            );
         }
+        *buf_size = positions.len();
+    } else {
+        unsafe {
+            window_info.gl.BindVertexArray(*vao);
+            window_info.gl.BindBuffer(gl::ARRAY_BUFFER, *buf_id);
+            window_info.gl.VertexAttribPointer(
+                0,
+                positions.len() as gl::types::GLint,
+                gl::FLOAT,
+                gl::FALSE,
+                (3 * std::mem::size_of::<f32>()) as gl::types::GLint,
+                std::ptr::null(),
+            );
+        }
+        unsafe {
+            window_info.gl.BindBuffer(gl::ARRAY_BUFFER, *buf_id);
+            window_info.gl.BufferSubData(
+                gl::ARRAY_BUFFER,
+                0,
+                (positions.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
+                positions.as_ptr() as *const gl::types::GLvoid,
+            );
+        }
+    }
+    if positions.len() != *buf_size {
+        *buf_size = positions.len() / 3;
+    }
 
-        if positions.len() > *buf_size {
-            unsafe {
-                window_info.gl.BindVertexArray(*vao);
-                window_info.gl.BindBuffer(gl::ARRAY_BUFFER, *buf_id);
-                window_info.gl.VertexAttribPointer(
-                    0,
-                    positions.len() as gl::types::GLint,
-                    gl::FLOAT,
-                    gl::FALSE,
-                    (3 * std::mem::size_of::<f32>()) as gl::types::GLint,
-                    std::ptr::null(),
-                );
-                window_info.gl.BufferData(
-                    gl::ARRAY_BUFFER,
-                    (positions.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
-                    positions.as_ptr() as *const gl::types::GLvoid,
-                    gl::DYNAMIC_DRAW,
-                );
-            }
-            *buf_size = positions.len();
-        } else {
-            unsafe {
-                window_info.gl.BindVertexArray(*vao);
-                window_info.gl.BindBuffer(gl::ARRAY_BUFFER, *buf_id);
-                window_info.gl.VertexAttribPointer(
-                    0,
-                    positions.len() as gl::types::GLint,
-                    gl::FLOAT,
-                    gl::FALSE,
-                    (3 * std::mem::size_of::<f32>()) as gl::types::GLint,
-                    std::ptr::null(),
-                );
-            }
-            unsafe {
-                window_info.gl.BindBuffer(gl::ARRAY_BUFFER, *buf_id);
-                window_info.gl.BufferSubData(
-                    gl::ARRAY_BUFFER,
-                    0,
-                    (positions.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
-                    positions.as_ptr() as *const gl::types::GLvoid,
-                );
+    positions.len() / 3
+}
+
+/* extends each particle's trail with where it is now, once per simulator step */
+pub fn update_trails(window_info: &mut window::WindowData) {
+    let length = window_info.trail_length;
+    let steps = window_info.simulator.borrow().steps();
+    if length == 0 || steps < window_info.trail_step {
+        window_info.trails.clear();
+    }
+    if length == 0 || steps == window_info.trail_step {
+        window_info.trail_step = steps;
+        return;
+    }
+    window_info.trail_step = steps;
+
+    let sim = window_info.simulator.clone();
+    let sim = sim.borrow();
+    let mut old = std::mem::take(&mut window_info.trails);
+    let mut trails = HashMap::with_capacity(old.len());
+    for p in sim.particle_list.borrow().iter() {
+        let (a, b) = window_info.view_coordinates(p);
+        let mut trail = old.remove(&p.get_id()).unwrap_or_default();
+        if let Some(&(last_a, last_b)) = trail.back() {
+            // a jump across half the view is a wrap through the boundary, not motion
+            let (x, y) = window_info.to_screen(a, b);
+            let (last_x, last_y) = window_info.to_screen(last_a, last_b);
+            if (x - last_x).abs() > 1.0 || (y - last_y).abs() > 1.0 {
+                trail.clear();
             }
         }
-        if positions.len() != *buf_size {
-            *buf_size = positions.len() / 3;
+        trail.push_back((a, b));
+        while trail.len() > length {
+            trail.pop_front();
         }
+        trails.insert(p.get_id(), trail);
+    }
+    window_info.trails = trails;
+}
 
-        return positions.len() / 3;
+/* each trail as a line strip fading out towards its oldest point */
+pub fn draw_trails(window_info: &mut window::WindowData) {
+    if window_info.trail_program.id() == 0 {
+        let vertex_shader = gl_render::Shader::from_vertex_source(
+            &window_info.gl,
+            &CString::new(include_str!("assets/shaders/trail.vert")).unwrap(),
+        )
+        .unwrap();
+        let fragment_shader = gl_render::Shader::from_frag_source(
+            &window_info.gl,
+            &CString::new(include_str!("assets/shaders/trail.frag")).unwrap(),
+        )
+        .unwrap();
+        window_info.trail_program = gl_render::Program::from_shaders(
+            &window_info.gl,
+            &[vertex_shader, fragment_shader],
+        )
+        .unwrap();
+        unsafe {
+            window_info.gl.GenBuffers(1, &mut window_info.trail_vbo);
+            window_info.gl.GenVertexArrays(1, &mut window_info.trail_vao);
+            window_info.gl.BindVertexArray(window_info.trail_vao);
+            window_info
+                .gl
+                .BindBuffer(gl::ARRAY_BUFFER, window_info.trail_vbo);
+            // x, y, z and alpha per point
+            window_info.gl.EnableVertexAttribArray(0);
+            window_info.gl.VertexAttribPointer(
+                0,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                (4 * std::mem::size_of::<f32>()) as gl::types::GLint,
+                std::ptr::null(),
+            );
+            window_info.gl.EnableVertexAttribArray(1);
+            window_info.gl.VertexAttribPointer(
+                1,
+                1,
+                gl::FLOAT,
+                gl::FALSE,
+                (4 * std::mem::size_of::<f32>()) as gl::types::GLint,
+                (3 * std::mem::size_of::<f32>()) as *const gl::types::GLvoid,
+            );
+        }
     }
 
-    pub fn draw_vectors(window_info: &mut window::WindowData) {
-        if window_info.vectors_require_update {
-            window_info.vector_program = gl_render::Program::blank_program(&window_info.gl);
-            window_info.vector_color_storage = Vec::new();
-            window_info.vector_coord_storage = Vec::new();
-            window_info.vector_vao = 0;
-            window_info.vector_vbo = [0, 0, 0];
-            window_info.vectors_require_update = false;
+    let mut vertices: Vec<f32> = Vec::new();
+    let mut firsts: Vec<gl::types::GLint> = Vec::new();
+    let mut counts: Vec<gl::types::GLsizei> = Vec::new();
+    for trail in window_info.trails.values().filter(|t| t.len() > 1) {
+        firsts.push((vertices.len() / 4) as gl::types::GLint);
+        counts.push(trail.len() as gl::types::GLsizei);
+        for (i, &(a, b)) in trail.iter().enumerate() {
+            vertices.extend(&[a, b, 0.0, (i + 1) as f32 / trail.len() as f32]);
         }
-        if window_info.vector_program.id() == 0 {
-            let vertex_shader = gl_render::Shader::from_vertex_source(
-                &window_info.gl,
-                &CString::new(include_str!("assets/shaders/vector.vert")).unwrap(),
-            )
-            .unwrap();
-            let fragment_shader = gl_render::Shader::from_frag_source(
-                &window_info.gl,
-                &CString::new(include_str!("assets/shaders/vector.frag")).unwrap(),
-            )
-            .unwrap();
-
-            // vectors
-            let mut max_mag = 0.0_f32;
-            let simulator = window_info.simulator.borrow_mut();
-            window_info.vector_program = gl_render::Program::from_shaders(
-                &window_info.gl,
-                &mut [vertex_shader, fragment_shader],
-            )
-            .unwrap();
-            for c in (((-window_info.x_range) as isize)..(window_info.y_range as isize)).step_by(2)
-            {
-                for r in
-                    (((-window_info.y_range) as isize)..(window_info.y_range as isize)).step_by(2)
-                {
-                    let force = Simulator::acceleration_for(
-                        &simulator,
-                        &Particle::new(c as f32, r as f32, 0.0, 0.0),
-                    );
-                    let theta = force.y.atan2(force.x);
-                    let mag = (force.x.powi(2) + force.y.powi(2)).sqrt() as f32;
-                    if mag > max_mag {
-                        max_mag = mag;
-                    }
+    }
+    if counts.is_empty() {
+        return;
+    }
 
-                    // starts at a point
-                    window_info
-                        .vector_coord_storage
-                        .push(c as f32 / window_info.x_range);
-                    window_info
-                        .vector_coord_storage
-                        .push(r as f32 / window_info.y_range);
-                    window_info.vector_coord_storage.push(0.0);
-                    window_info.vector_color_storage.push(mag.abs());
-
-                    // goes at some angle
-                    // radius always == 1
-                    if mag != 0.0 {
-                        window_info.vector_coord_storage.push(
-                            (theta.cos() as f32 * 0.8 / window_info.x_range)
-                                + (c as f32 / window_info.x_range),
-                        );
-                        window_info.vector_coord_storage.push(
-                            (theta.sin() as f32 * 0.8 / window_info.y_range)
-                                + (r as f32 / window_info.y_range),
-                        );
-                    } else {
-                        window_info
-                            .vector_coord_storage
-                            .push(c as f32 / window_info.x_range);
-                        window_info
-                            .vector_coord_storage
-                            .push(r as f32 / window_info.y_range);
-                    }
-                    window_info.vector_coord_storage.push(0.0);
+    window_info.trail_program.set_used();
+    set_camera(window_info, &window_info.trail_program);
+    let vertex_color_location = unsafe {
+        window_info.gl.GetUniformLocation(
+            window_info.trail_program.id(),
+            (CString::new("inColor").unwrap()).as_ptr(),
+        )
+    };
+    unsafe {
+        window_info.gl.Enable(gl::BLEND);
+        window_info
+            .gl
+            .BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        window_info
+            .gl
+            .Uniform4f(vertex_color_location, 0.2, 0.3, 0.7, 0.8);
+        window_info.gl.LineWidth(1.5);
+        window_info.gl.BindVertexArray(window_info.trail_vao);
+        window_info
+            .gl
+            .BindBuffer(gl::ARRAY_BUFFER, window_info.trail_vbo);
+        window_info.gl.BufferData(
+            gl::ARRAY_BUFFER,
+            (vertices.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
+            vertices.as_ptr() as *const gl::types::GLvoid,
+            gl::DYNAMIC_DRAW,
+        );
+        window_info.gl.MultiDrawArrays(
+            gl::LINE_STRIP,
+            firsts.as_ptr(),
+            counts.as_ptr(),
+            counts.len() as gl::types::GLsizei,
+        );
+    }
+}
 
-                    window_info.vector_color_storage.push(mag.abs());
-                }
-            }
-            window_info.vec_range = max_mag;
+pub fn draw_vectors(window_info: &mut window::WindowData) {
+    // the field changed since it was last sampled, changes on its own, or the
+    // camera moved over a different part of it
+    let revision = window_info.simulator.borrow().field_revision();
+    let camera = window_info.camera_transform();
+    let stale = window_info.vector_revision != revision
+        || window_info.vector_camera != camera
+        || window_info.simulator.borrow().field_depends_on_time();
+    window_info.vector_revision = revision;
+    window_info.vector_camera = camera;
+    if window_info.vectors_require_update {
+        window_info.vector_program = gl_render::Program::blank_program(&window_info.gl);
+        window_info.vector_color_storage = Vec::new();
+        window_info.vector_coord_storage = Vec::new();
+        window_info.vector_vao = 0;
+        window_info.vector_vbo = [0, 0, 0];
+        window_info.vectors_require_update = false;
+    }
+    if window_info.vector_program.id() == 0 {
+        let vertex_shader = gl_render::Shader::from_vertex_source(
+            &window_info.gl,
+            &CString::new(include_str!("assets/shaders/vector.vert")).unwrap(),
+        )
+        .unwrap();
+        let fragment_shader = gl_render::Shader::from_frag_source(
+            &window_info.gl,
+            &CString::new(include_str!("assets/shaders/vector.frag")).unwrap(),
+        )
+        .unwrap();
 
-            unsafe {
-                window_info.gl.GenBuffers(3, &mut window_info.vector_vbo[0]);
-                window_info
-                    .gl
-                    .BindBuffer(gl::ARRAY_BUFFER, window_info.vector_vbo[0]);
-                window_info.gl.BufferData(
-                    gl::ARRAY_BUFFER,
-                    (window_info.vector_coord_storage.len() * std::mem::size_of::<f32>())
-                        as gl::types::GLsizeiptr,
-                    window_info.vector_coord_storage.as_ptr() as *const gl::types::GLvoid,
-                    gl::DYNAMIC_DRAW,
-                );
-                window_info
-                    .gl
-                    .BindBuffer(gl::ARRAY_BUFFER, window_info.vector_vbo[1]);
-                window_info.gl.BufferData(
-                    gl::ARRAY_BUFFER,
-                    (window_info.vector_color_storage.len() * std::mem::size_of::<f32>())
-                        as gl::types::GLsizeiptr,
-                    window_info.vector_color_storage.as_ptr() as *const gl::types::GLvoid,
-                    gl::STATIC_DRAW,
-                );
-                window_info
-                    .gl
-                    .GenVertexArrays(1, &mut window_info.vector_vao);
-                window_info.gl.BindVertexArray(window_info.vector_vao);
-                window_info
-                    .gl
-                    .BindBuffer(gl::ARRAY_BUFFER, window_info.vector_vbo[0]);
-                window_info.gl.EnableVertexAttribArray(0);
-                window_info.gl.VertexAttribPointer(
-                    0,
-                    3,
-                    gl::FLOAT,
-                    gl::FALSE,
-                    (3 * std::mem::size_of::<f32>()) as gl::types::GLint,
-                    std::ptr::null(),
-                );
-                window_info
-                    .gl
-                    .BindBuffer(gl::ARRAY_BUFFER, window_info.vector_vbo[1]);
-                window_info.gl.EnableVertexAttribArray(1);
-                window_info.gl.VertexAttribPointer(
-                    1,
-                    1,
-                    gl::FLOAT,
-                    gl::FALSE,
-                    std::mem::size_of::<f32>() as gl::types::GLint,
-                    std::ptr::null(),
-                );
-                window_info.gl.BindBuffer(gl::ARRAY_BUFFER, 0);
-                window_info.gl.BindVertexArray(0);
-            }
-        }
-        window_info.vector_program.set_used();
-        let range_location = unsafe {
-            window_info.gl.GetUniformLocation(
-                window_info.vector_program.id(),
-                (CString::new("in_range").unwrap()).as_ptr(),
-            )
-        };
+        window_info.vector_program = gl_render::Program::from_shaders(
+            &window_info.gl,
+            &[vertex_shader, fragment_shader],
+        )
+        .unwrap();
+        sample_vectors(window_info);
 
         unsafe {
-            window_info.gl.LineWidth(2.0);
+            window_info.gl.GenBuffers(3, &mut window_info.vector_vbo[0]);
             window_info
                 .gl
-                .Uniform1f(range_location, window_info.vec_range);
+                .BindBuffer(gl::ARRAY_BUFFER, window_info.vector_vbo[0]);
+            window_info.gl.BufferData(
+                gl::ARRAY_BUFFER,
+                (window_info.vector_coord_storage.len() * std::mem::size_of::<f32>())
+                    as gl::types::GLsizeiptr,
+                window_info.vector_coord_storage.as_ptr() as *const gl::types::GLvoid,
+                gl::DYNAMIC_DRAW,
+            );
+            window_info
+                .gl
+                .BindBuffer(gl::ARRAY_BUFFER, window_info.vector_vbo[1]);
+            window_info.gl.BufferData(
+                gl::ARRAY_BUFFER,
+                (window_info.vector_color_storage.len() * std::mem::size_of::<f32>())
+                    as gl::types::GLsizeiptr,
+                window_info.vector_color_storage.as_ptr() as *const gl::types::GLvoid,
+                gl::DYNAMIC_DRAW,
+            );
+            window_info
+                .gl
+                .GenVertexArrays(1, &mut window_info.vector_vao);
             window_info.gl.BindVertexArray(window_info.vector_vao);
-            window_info.gl.DrawArrays(
-                gl::LINES,
+            window_info
+                .gl
+                .BindBuffer(gl::ARRAY_BUFFER, window_info.vector_vbo[0]);
+            window_info.gl.EnableVertexAttribArray(0);
+            window_info.gl.VertexAttribPointer(
                 0,
-                (window_info.vector_coord_storage.len() / 3) as i32,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                (3 * std::mem::size_of::<f32>()) as gl::types::GLint,
+                std::ptr::null(),
+            );
+            window_info
+                .gl
+                .BindBuffer(gl::ARRAY_BUFFER, window_info.vector_vbo[1]);
+            window_info.gl.EnableVertexAttribArray(1);
+            window_info.gl.VertexAttribPointer(
+                1,
+                1,
+                gl::FLOAT,
+                gl::FALSE,
+                std::mem::size_of::<f32>() as gl::types::GLint,
+                std::ptr::null(),
+            );
+            window_info.gl.BindBuffer(gl::ARRAY_BUFFER, 0);
+            window_info.gl.BindVertexArray(0);
+        }
+    } else if stale {
+        // zooming changes how many arrows there are, so the buffers are resized
+        sample_vectors(window_info);
+        unsafe {
+            window_info
+                .gl
+                .BindBuffer(gl::ARRAY_BUFFER, window_info.vector_vbo[0]);
+            window_info.gl.BufferData(
+                gl::ARRAY_BUFFER,
+                (window_info.vector_coord_storage.len() * std::mem::size_of::<f32>())
+                    as gl::types::GLsizeiptr,
+                window_info.vector_coord_storage.as_ptr() as *const gl::types::GLvoid,
+                gl::DYNAMIC_DRAW,
             );
+            window_info
+                .gl
+                .BindBuffer(gl::ARRAY_BUFFER, window_info.vector_vbo[1]);
+            window_info.gl.BufferData(
+                gl::ARRAY_BUFFER,
+                (window_info.vector_color_storage.len() * std::mem::size_of::<f32>())
+                    as gl::types::GLsizeiptr,
+                window_info.vector_color_storage.as_ptr() as *const gl::types::GLvoid,
+                gl::DYNAMIC_DRAW,
+            );
+            window_info.gl.BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+    }
+    window_info.vector_program.set_used();
+    set_camera(window_info, &window_info.vector_program);
+    let range_location = unsafe {
+        window_info.gl.GetUniformLocation(
+            window_info.vector_program.id(),
+            (CString::new("in_range").unwrap()).as_ptr(),
+        )
+    };
+
+    unsafe {
+        window_info.gl.LineWidth(2.0);
+        window_info
+            .gl
+            .Uniform1f(range_location, window_info.vec_range);
+        window_info.gl.BindVertexArray(window_info.vector_vao);
+        window_info.gl.DrawArrays(
+            gl::LINES,
+            0,
+            (window_info.vector_coord_storage.len() / 3) as i32,
+        );
+    }
+}
+
+/* samples the field's acceleration on a grid across the view into the vector
+storage, as line segments colored by magnitude */
+fn sample_vectors(window_info: &mut window::WindowData) {
+    let mut max_mag = 0.0_f32;
+    let ((left, bottom), (right, top)) = window_info.visible_extent();
+    // twice the gridline spacing, so an arrow sits on every other crossing
+    let spacing = 2.0 * grid_spacing(right - left);
+    let length = 0.4 * spacing;
+    let simulator = window_info.simulator.borrow();
+    let time = simulator.time();
+    window_info.vector_coord_storage.clear();
+    window_info.vector_color_storage.clear();
+    for c in ((left / spacing).ceil() as isize)..=((right / spacing).floor() as isize) {
+        for r in ((bottom / spacing).ceil() as isize)..=((top / spacing).floor() as isize) {
+            let (x, y) = (c as f32 * spacing, r as f32 * spacing);
+            let force = Simulator::acceleration_for(&simulator, &Particle::new(x, y, 0.0, 0.0), time);
+            let theta = force.y.atan2(force.x);
+            let mag = (force.x.powi(2) + force.y.powi(2)).sqrt();
+            if mag > max_mag {
+                max_mag = mag;
+            }
+
+            // starts at a point
+            window_info.vector_coord_storage.extend(&[x, y, 0.0]);
+            window_info.vector_color_storage.push(mag.abs());
+
+            // goes at some angle
+            if mag != 0.0 {
+                window_info.vector_coord_storage.extend(&[
+                    x + theta.cos() * length,
+                    y + theta.sin() * length,
+                    0.0,
+                ]);
+            } else {
+                window_info.vector_coord_storage.extend(&[x, y, 0.0]);
+            }
+
+            window_info.vector_color_storage.push(mag.abs());
         }
     }
+    window_info.vec_range = max_mag;
 }
 
 pub fn draw_gridlines(window_info: &mut window::WindowData) {
     if window_info.gridline_program.id() == 0 {
         let vertex_shader = gl_render::Shader::from_vertex_source(
             &window_info.gl,
-            &CString::new(include_str!("assets/shaders/line.vert")).unwrap(),
+            &CString::new(include_str!("assets/shaders/gridline.vert")).unwrap(),
         )
         .unwrap();
         let fragment_shader = gl_render::Shader::from_frag_source(
@@ -246,42 +424,52 @@ pub fn draw_gridlines(window_info: &mut window::WindowData) {
 
         window_info.gridline_program = gl_render::Program::from_shaders(
             &window_info.gl,
-            &mut [vertex_shader, fragment_shader],
+            &[vertex_shader, fragment_shader],
         )
         .unwrap();
-        for v in ((-window_info.x_range) as isize)..(window_info.x_range as isize) {
+        unsafe {
+            window_info.gl.GenBuffers(1, &mut window_info.gridline_vbo);
             window_info
-                .gridline_vec
-                .push((v as f32) / window_info.x_range);
-            window_info.gridline_vec.push(1.0);
-            window_info.gridline_vec.push(0.0);
-
+                .gl
+                .GenVertexArrays(1, &mut window_info.gridline_vao);
+            window_info.gl.BindVertexArray(window_info.gridline_vao);
             window_info
-                .gridline_vec
-                .push(v as f32 / window_info.x_range);
-            window_info.gridline_vec.push(-1.0);
-            window_info.gridline_vec.push(0.0);
+                .gl
+                .BindBuffer(gl::ARRAY_BUFFER, window_info.gridline_vbo);
+            window_info.gl.EnableVertexAttribArray(0);
+            window_info.gl.VertexAttribPointer(
+                0,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                (3 * std::mem::size_of::<f32>()) as gl::types::GLint,
+                std::ptr::null(),
+            );
         }
-        for v in ((-window_info.y_range) as isize + 1)..(window_info.y_range as isize) {
-            window_info.gridline_vec.push(-1.0);
-            window_info
-                .gridline_vec
-                .push(v as f32 / window_info.y_range);
-            window_info.gridline_vec.push(0.0);
+    }
 
-            window_info.gridline_vec.push(1.0);
-            window_info
-                .gridline_vec
-                .push((v as f32) / window_info.y_range);
-            window_info.gridline_vec.push(0.0);
+    // lay the lines out again whenever the camera moves, spaced to suit the zoom
+    let camera = window_info.camera_transform();
+    if window_info.gridline_camera != camera || window_info.gridline_vec.is_empty() {
+        window_info.gridline_camera = camera;
+        let ((left, bottom), (right, top)) = window_info.visible_extent();
+        let spacing_a = grid_spacing(right - left);
+        let spacing_b = grid_spacing(top - bottom);
+        let lines = &mut window_info.gridline_vec;
+        lines.clear();
+        for v in ((left / spacing_a).ceil() as isize)..=((right / spacing_a).floor() as isize) {
+            let a = v as f32 * spacing_a;
+            lines.extend(&[a, bottom, 0.0, a, top, 0.0]);
+        }
+        for v in ((bottom / spacing_b).ceil() as isize)..=((top / spacing_b).floor() as isize) {
+            let b = v as f32 * spacing_b;
+            lines.extend(&[left, b, 0.0, right, b, 0.0]);
         }
-        window_info.gridline_vec.extend(&[-1.0, 0.0, 0.0]);
-        window_info.gridline_vec.extend(&[1.0, 0.0, 0.0]);
-        window_info.gridline_vec.extend(&[0.0, -1.0, 0.0]);
-        window_info.gridline_vec.extend(&[0.0, 1.0, 0.0]);
+        // the axes, drawn heavier
+        lines.extend(&[left, 0.0, 0.0, right, 0.0, 0.0]);
+        lines.extend(&[0.0, bottom, 0.0, 0.0, top, 0.0]);
 
         unsafe {
-            window_info.gl.GenBuffers(1, &mut window_info.gridline_vbo);
             window_info
                 .gl
                 .BindBuffer(gl::ARRAY_BUFFER, window_info.gridline_vbo);
@@ -290,24 +478,12 @@ pub fn draw_gridlines(window_info: &mut window::WindowData) {
                 (window_info.gridline_vec.len() * std::mem::size_of::<f32>())
                     as gl::types::GLsizeiptr,
                 window_info.gridline_vec.as_ptr() as *const gl::types::GLvoid,
-                gl::STATIC_DRAW,
-            );
-            window_info
-                .gl
-                .GenVertexArrays(1, &mut window_info.gridline_vao);
-            window_info.gl.BindVertexArray(window_info.gridline_vao);
-            window_info.gl.EnableVertexAttribArray(0);
-            window_info.gl.VertexAttribPointer(
-                0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                (3 * std::mem::size_of::<f32>()) as gl::types::GLint,
-                std::ptr::null(),
+                gl::DYNAMIC_DRAW,
             );
         }
     }
     window_info.gridline_program.set_used();
+    set_camera(window_info, &window_info.gridline_program);
     let vertex_color_location = unsafe {
         window_info.gl.GetUniformLocation(
             window_info.gridline_program.id(),
@@ -341,6 +517,248 @@ pub fn draw_gridlines(window_info: &mut window::WindowData) {
     }
 }
 
+/* outlines each field source: red for positive charge, blue for negative and black
+for masses, with a plus or minus inside point charges */
+pub fn draw_sources(window_info: &mut window::WindowData) {
+    // line segments in world coordinates, one list per color
+    let mut positive: Vec<f32> = Vec::new();
+    let mut negative: Vec<f32> = Vec::new();
+    let mut neutral: Vec<f32> = Vec::new();
+    let size = 0.6;
+    for source in window_info.simulator.borrow().sources() {
+        match source {
+            FieldSource::PointCharge { pos, charge } => {
+                let list = if *charge >= 0.0 {
+                    &mut positive
+                } else {
+                    &mut negative
+                };
+                push_square(list, pos.x, pos.y, size);
+                list.extend(&[pos.x - size / 2.0, pos.y, pos.x + size / 2.0, pos.y]);
+                if *charge >= 0.0 {
+                    list.extend(&[pos.x, pos.y - size / 2.0, pos.x, pos.y + size / 2.0]);
+                }
+            }
+            FieldSource::PointMass { pos, .. } => push_square(&mut neutral, pos.x, pos.y, size),
+            FieldSource::LineCharge {
+                start,
+                end,
+                density,
+            } => {
+                let list = if *density >= 0.0 {
+                    &mut positive
+                } else {
+                    &mut negative
+                };
+                list.extend(&[start.x, start.y, end.x, end.y]);
+            }
+        }
+    }
+
+    for (list, color) in &[
+        (positive, [0.85, 0.1, 0.1]),
+        (negative, [0.1, 0.2, 0.85]),
+        (neutral, [0.0, 0.0, 0.0]),
+    ] {
+        draw_world_lines(window_info, list, *color, 3.0);
+    }
+}
+
+/* emitters in green with an arrow for their mean launch velocity, sinks in purple */
+pub fn draw_flow(window_info: &mut window::WindowData) {
+    let mut emitters: Vec<f32> = Vec::new();
+    let mut sinks: Vec<f32> = Vec::new();
+    {
+        let sim = window_info.simulator.borrow();
+        for emitter in sim.emitters() {
+            match &emitter.shape {
+                EmitterShape::Point { pos } => push_square(&mut emitters, pos.x, pos.y, 0.3),
+                EmitterShape::Line { start, end } => {
+                    emitters.extend(&[start.x, start.y, end.x, end.y])
+                }
+            }
+            let center = emitter.center();
+            let speed = emitter.speed.mean();
+            let (tip_x, tip_y) = (
+                center.x + speed * emitter.direction.cos(),
+                center.y + speed * emitter.direction.sin(),
+            );
+            emitters.extend(&[center.x, center.y, tip_x, tip_y]);
+            for side in &[-0.5, 0.5] {
+                let angle = emitter.direction + std::f32::consts::PI + side;
+                emitters.extend(&[
+                    tip_x,
+                    tip_y,
+                    tip_x + 0.5 * angle.cos(),
+                    tip_y + 0.5 * angle.sin(),
+                ]);
+            }
+        }
+        for sink in sim.sinks() {
+            match &sink.shape {
+                SinkShape::Circle { center, radius } => {
+                    let segments = 32;
+                    for i in 0..segments {
+                        let a = i as f32 / segments as f32 * 2.0 * std::f32::consts::PI;
+                        let b = (i + 1) as f32 / segments as f32 * 2.0 * std::f32::consts::PI;
+                        sinks.extend(&[
+                            center.x + radius * a.cos(),
+                            center.y + radius * a.sin(),
+                            center.x + radius * b.cos(),
+                            center.y + radius * b.sin(),
+                        ]);
+                    }
+                }
+                SinkShape::Rect { min, max } => sinks.extend(&[
+                    min.x, min.y, max.x, min.y,
+                    max.x, min.y, max.x, max.y,
+                    max.x, max.y, min.x, max.y,
+                    min.x, max.y, min.x, min.y,
+                ]),
+            }
+        }
+    }
+    draw_world_lines(window_info, &emitters, [0.0, 0.6, 0.2], 2.0);
+    draw_world_lines(window_info, &sinks, [0.5, 0.1, 0.6], 2.0);
+}
+
+/* a box around each selected particle, the same size on screen at any zoom */
+pub fn draw_selection(window_info: &mut window::WindowData, selected: &[u32]) {
+    let transform = window_info.camera_transform();
+    let (half_a, half_b) = (0.025 / transform[2], 0.025 / transform[3]);
+    let mut list: Vec<f32> = Vec::new();
+    for p in window_info.simulator.borrow().particle_list.borrow().iter() {
+        if selected.contains(&p.get_id()) {
+            let (a, b) = window_info.view_coordinates(p);
+            list.extend(&[
+                a - half_a, b - half_b, a + half_a, b - half_b,
+                a + half_a, b - half_b, a + half_a, b + half_b,
+                a + half_a, b + half_b, a - half_a, b + half_b,
+                a - half_a, b + half_b, a - half_a, b - half_b,
+            ]);
+        }
+    }
+    draw_world_lines(window_info, &list, [1.0, 0.55, 0.0], 2.0);
+}
+
+/* the spring from a grabbed particle to the cursor, only meaningful in space view */
+pub fn draw_joint(window_info: &mut window::WindowData) {
+    if window_info.view_mode != window::ViewMode::Space {
+        return;
+    }
+    let mut list: Vec<f32> = Vec::new();
+    {
+        let sim = window_info.simulator.borrow();
+        if let Some(joint) = sim.joint() {
+            if let Some(p) = sim.particle(joint.particle) {
+                let pos = p.get_pos();
+                list.extend(&[pos.x, pos.y, joint.target.x, joint.target.y]);
+            }
+        }
+    }
+    draw_world_lines(window_info, &list, [0.2, 0.7, 0.3], 2.0);
+}
+
+/* line segments, as pairs of (x, y) endpoints in view coordinates, seen through the
+camera */
+fn draw_world_lines(
+    window_info: &mut window::WindowData,
+    list: &[f32],
+    color: [f32; 3],
+    width: f32,
+) {
+    if list.is_empty() {
+        return;
+    }
+    if window_info.source_program.id() == 0 {
+        let vertex_shader = gl_render::Shader::from_vertex_source(
+            &window_info.gl,
+            &CString::new(include_str!("assets/shaders/gridline.vert")).unwrap(),
+        )
+        .unwrap();
+        let fragment_shader = gl_render::Shader::from_frag_source(
+            &window_info.gl,
+            &CString::new(include_str!("assets/shaders/line.frag")).unwrap(),
+        )
+        .unwrap();
+        window_info.source_program = gl_render::Program::from_shaders(
+            &window_info.gl,
+            &[vertex_shader, fragment_shader],
+        )
+        .unwrap();
+        unsafe {
+            window_info.gl.GenBuffers(1, &mut window_info.source_vbo);
+            window_info.gl.GenVertexArrays(1, &mut window_info.source_vao);
+            window_info.gl.BindVertexArray(window_info.source_vao);
+            window_info
+                .gl
+                .BindBuffer(gl::ARRAY_BUFFER, window_info.source_vbo);
+            window_info.gl.EnableVertexAttribArray(0);
+            window_info.gl.VertexAttribPointer(
+                0,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                (3 * std::mem::size_of::<f32>()) as gl::types::GLint,
+                std::ptr::null(),
+            );
+        }
+    }
+
+    window_info.source_program.set_used();
+    set_camera(window_info, &window_info.source_program);
+    let vertex_color_location = unsafe {
+        window_info.gl.GetUniformLocation(
+            window_info.source_program.id(),
+            (CString::new("inColor").unwrap()).as_ptr(),
+        )
+    };
+    let coordinates: Vec<f32> = list
+        .chunks(2)
+        .flat_map(|c| vec![c[0], c[1], 0.0])
+        .collect();
+    unsafe {
+        window_info
+            .gl
+            .Uniform4f(vertex_color_location, color[0], color[1], color[2], 1.0);
+        window_info.gl.LineWidth(width);
+        window_info.gl.BindVertexArray(window_info.source_vao);
+        window_info
+            .gl
+            .BindBuffer(gl::ARRAY_BUFFER, window_info.source_vbo);
+        window_info.gl.BufferData(
+            gl::ARRAY_BUFFER,
+            (coordinates.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
+            coordinates.as_ptr() as *const gl::types::GLvoid,
+            gl::DYNAMIC_DRAW,
+        );
+        window_info.gl.DrawArrays(gl::LINES, 0, (coordinates.len() / 3) as i32);
+    }
+}
+
+/* a round gridline spacing, 1, 2 or 5 times a power of ten, giving about 60 lines
+across the extent, which is one unit at the default zoom */
+fn grid_spacing(extent: f32) -> f32 {
+    let raw = (extent / 60.0).max(1e-6);
+    let power = 10.0_f32.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * power)
+        .find(|s| *s >= raw * 0.999)
+        .unwrap_or(10.0 * power)
+}
+
+/* the outline of a square centered on (x, y), as line segment endpoints */
+fn push_square(list: &mut Vec<f32>, x: f32, y: f32, half: f32) {
+    list.extend(&[
+        x - half, y - half, x + half, y - half,
+        x + half, y - half, x + half, y + half,
+        x + half, y + half, x - half, y + half,
+        x - half, y + half, x - half, y - half,
+    ]);
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn render_text(
     gl: &gl::Gl,
     text: &str,
@@ -353,7 +771,7 @@ pub fn render_text(
     y: f32,
     scale: f32,
 ) {
-    let glyphs = (&text).as_bytes();
+    let glyphs = text.as_bytes();
     unsafe { gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1) };
     // initialization
     program.set_used();
@@ -370,7 +788,7 @@ pub fn render_text(
     }
 
     for glyph in glyphs {
-        if !window_info.character_map.contains_key(&glyph) {
+        if !window_info.character_map.contains_key(glyph) {
             font.load_char(*glyph as usize, freetype::face::LoadFlag::RENDER).unwrap();
             let g = font.glyph();
             let mut texture: gl::types::GLuint = 0;
@@ -400,10 +818,10 @@ pub fn render_text(
                 size_y: g.bitmap().rows(),
                 bearing_x: g.bitmap_left(),
                 bearing_y: g.bitmap_top(),
-                advance: g.advance().x.into()
+                advance: g.advance().x
             });
         }
-        let c = window_info.character_map.get(&glyph).unwrap();
+        let c = window_info.character_map.get(glyph).unwrap();
 
         let x_pos = x + (c.bearing_x as f32) * scale;
         let y_pos = y - (c.size_y - c.bearing_y) as f32 * scale;
@@ -14,7 +14,7 @@ pub struct Program {
 impl Program {
 
     pub fn id(&self) -> gl::types::GLuint {
-        return self.id;
+        self.id
     }
 
     pub fn blank_program(gl: &gl::Gl) -> Program {
@@ -62,7 +62,7 @@ impl Program {
 
             return Err(String::from_utf8(error).unwrap());
         }
-        Ok(Program { gl: gl.clone(), id: id })
+        Ok(Program { gl: gl.clone(), id })
     }
 
     pub fn set_used(&self) {
@@ -85,7 +85,7 @@ impl Shader {
     fn from_source(gl: &gl::Gl, src: &CStr, kind: gl::types::GLenum) -> Result<Shader, String> {
         Ok(Shader {
             gl: gl.clone(),
-            id: shader_from_source(&gl, src, kind).unwrap()
+            id: shader_from_source(gl, src, kind).unwrap()
         })
     }
 
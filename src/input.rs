@@ -13,22 +13,25 @@ pub struct Button {
     pub vao_id: gl::types::GLuint,
     pub vbo_id: gl::types::GLuint,
     pub coordinates: Vec<f32>,
-    pub onclick: String
+    pub onclick: String,
+    // toggles that are switched on get a darker fill
+    pub active: bool,
 }
 
 impl Button {
     pub fn new(gl: &gl::Gl, x: f32, y: f32, width: f32, height: f32, onclick: String) -> Button {
         Button {
             gl: gl.clone(),
-            x: x,
-            y: y,
-            width: width,
-            height: height,
-            program: Program::blank_program(&gl),
+            x,
+            y,
+            width,
+            height,
+            program: Program::blank_program(gl),
             vao_id: 0,
             vbo_id: 0,
             coordinates: Vec::new(),
-            onclick: onclick,
+            onclick,
+            active: false,
         }
     }
 
@@ -135,7 +138,11 @@ impl Button {
             )
         };
         unsafe {
-            self.gl.Uniform4f(vertex_color_location, 0.95, 0.95, 0.95, 0.8);
+            if self.active {
+                self.gl.Uniform4f(vertex_color_location, 0.75, 0.85, 0.95, 0.8);
+            } else {
+                self.gl.Uniform4f(vertex_color_location, 0.95, 0.95, 0.95, 0.8);
+            }
             self.gl.LineWidth(1.0);
             self.gl.BindVertexArray(self.vao_id);
             self.gl.DrawArrays(gl::TRIANGLE_FAN, 0, (self.coordinates.len() / 3) as i32 - 8);
@@ -144,3 +151,117 @@ impl Button {
         }
     }
 }
+
+/* a horizontal track with a draggable knob, bound to a named simulator parameter */
+pub struct Slider {
+    gl: gl::Gl,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub program: Program,
+    pub vao_id: gl::types::GLuint,
+    pub vbo_id: gl::types::GLuint,
+    // empty when the slider isn't bound to anything
+    pub parameter: String,
+}
+
+impl Slider {
+    pub fn new(gl: &gl::Gl, x: f32, y: f32, width: f32, height: f32, parameter: String) -> Slider {
+        Slider {
+            gl: gl.clone(),
+            x,
+            y,
+            width,
+            height,
+            program: Program::blank_program(gl),
+            vao_id: 0,
+            vbo_id: 0,
+            parameter,
+        }
+    }
+
+    pub fn parameter(&self) -> &str {
+        &self.parameter
+    }
+
+    pub fn contains(&self, c_x: f32, c_y: f32) -> bool {
+        c_x > self.x && c_y < self.y && c_x < self.x + self.width && c_y > self.y - self.height
+    }
+
+    /* the value under the cursor, clamped to [min, max] */
+    pub fn value_at(&self, c_x: f32, min: f32, max: f32) -> f32 {
+        let fraction = ((c_x - self.x) / self.width).clamp(0.0, 1.0);
+        min + fraction * (max - min)
+    }
+
+    /* fraction is where the knob sits along the track, 0 at the left */
+    pub fn draw(&mut self, fraction: f32) {
+        if self.program.id() == 0 {
+            let vertex_shader = gl_render::Shader::from_vertex_source(
+                &self.gl,
+                &CString::new(include_str!("assets/shaders/line.vert")).unwrap(),
+            )
+            .unwrap();
+            let fragment_shader = gl_render::Shader::from_frag_source(
+                &self.gl,
+                &CString::new(include_str!("assets/shaders/line.frag")).unwrap(),
+            )
+            .unwrap();
+            self.program =
+                gl_render::Program::from_shaders(&self.gl, &[vertex_shader, fragment_shader]).unwrap();
+            unsafe {
+                self.gl.GenBuffers(1, &mut self.vbo_id);
+                self.gl.GenVertexArrays(1, &mut self.vao_id);
+                self.gl.BindVertexArray(self.vao_id);
+                self.gl.BindBuffer(gl::ARRAY_BUFFER, self.vbo_id);
+                self.gl.EnableVertexAttribArray(0);
+                self.gl.VertexAttribPointer(
+                    0,
+                    3,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    (3 * std::mem::size_of::<f32>()) as gl::types::GLint,
+                    std::ptr::null(),
+                );
+            }
+        }
+
+        let middle = self.y - self.height / 2.0;
+        let knob_x = self.x + fraction.clamp(0.0, 1.0) * self.width;
+        let knob_half = 0.01;
+        let coordinates: [f32; 18] = [
+            // track
+            self.x, middle, 0.0,
+            self.x + self.width, middle, 0.0,
+            // knob
+            knob_x - knob_half, self.y, 0.0,
+            knob_x + knob_half, self.y, 0.0,
+            knob_x + knob_half, self.y - self.height, 0.0,
+            knob_x - knob_half, self.y - self.height, 0.0,
+        ];
+
+        self.program.set_used();
+        let vertex_color_location = unsafe {
+            self.gl.GetUniformLocation(
+                self.program.id(),
+                (CString::new("inColor").unwrap()).as_ptr(),
+            )
+        };
+        unsafe {
+            self.gl.BindVertexArray(self.vao_id);
+            self.gl.BindBuffer(gl::ARRAY_BUFFER, self.vbo_id);
+            self.gl.BufferData(
+                gl::ARRAY_BUFFER,
+                (coordinates.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
+                coordinates.as_ptr() as *const gl::types::GLvoid,
+                gl::DYNAMIC_DRAW,
+            );
+            self.gl.LineWidth(1.0);
+            self.gl.Uniform4f(vertex_color_location, 0.4, 0.4, 0.4, 1.0);
+            self.gl.DrawArrays(gl::LINES, 0, 2);
+            self.gl.Uniform4f(vertex_color_location, 0.1, 0.1, 0.1, 1.0);
+            self.gl.DrawArrays(gl::TRIANGLE_FAN, 2, 4);
+        }
+    }
+}
@@ -0,0 +1,14 @@
+// physics core, usable without SDL2/OpenGL
+pub mod simulator;
+
+// window and rendering, enabled by the `gui` feature
+#[cfg(feature = "gui")]
+pub mod gl_draw;
+#[cfg(feature = "gui")]
+pub mod gl_render;
+#[cfg(feature = "gui")]
+pub mod input;
+#[cfg(feature = "gui")]
+pub mod plot;
+#[cfg(feature = "gui")]
+pub mod window;
@@ -1,21 +1,53 @@
-use freetype;
-use gl;
-use sdl2;
 use std::cell::RefCell;
 use std::cmp;
 use std::ffi::CString;
 use std::rc::Rc;
 use std::f32::consts::PI;
 
-mod gl_draw;
-mod gl_render;
-mod input;
-mod simulator;
-mod window;
-
+use sdl_physics::{gl_draw, gl_render, input, plot, simulator, window};
+use simulator::boundary::{Boundary, BoundaryMode};
+use simulator::emitters::{Emitter, Sink};
+use simulator::expression::FieldExpression;
+use simulator::integrator;
+use simulator::sources::FieldSource;
+use simulator::interaction::{Interaction, Solver};
 use simulator::util::PhysVector;
 use simulator::Simulator;
 
+// what a left click on empty space places
+const PLACE_TOOLS: [&str; 8] = [
+    "Particle",
+    "+ Charge",
+    "- Charge",
+    "Mass",
+    "Line Charge",
+    "Emitter",
+    "Line Emitter",
+    "Sink",
+];
+// points kept per particle trail, cycled by the Trails button
+const TRAIL_LENGTHS: [usize; 4] = [0, 30, 100, 300];
+// how far, in pixels, the mouse can move during a right click before it's a pan
+const PAN_THRESHOLD: i32 = 3;
+// zoom factor per notch of the mouse wheel
+const ZOOM_STEP: f32 = 1.15;
+// how close, in world units, a click has to be to grab a field source
+const SOURCE_PICK_RADIUS: f32 = 1.0;
+// how close a click has to be to select a particle, in world units at the default zoom
+const PARTICLE_PICK_RADIUS: f32 = 0.8;
+// vertical distance between sliders when they all fit
+const SLIDER_SPACING: f32 = 0.08;
+// launch speed of line emitters placed with the mouse, their drag is the line itself
+const LINE_EMITTER_SPEED: f32 = 5.0;
+
+// (label, mass, charge) for particles created by click-dragging
+const PARTICLE_PRESETS: [(&str, f32, f32); 4] = [
+    ("Positive", 1.0, 1.0),
+    ("Negative", 1.0, -1.0),
+    ("Heavy", 10.0, 1.0),
+    ("Neutral", 1.0, 0.0),
+];
+
 fn main() {
     let sdl = sdl2::init().unwrap();
     let video_subsystem = sdl.video().unwrap();
@@ -32,10 +64,10 @@ fn main() {
 
     let (wsize_x, wsize_y) = window.size();
 
-    let (mut x, mut y): (i32, i32) = (wsize_x as i32, wsize_y as i32);
-    let mut width: i32 = cmp::min(x as i32, y as i32);
-    let mut x_offset = (x - width) / 2;
-    let mut y_offset = (y - width) / 2;
+    // the world fills the window, the button panel is a square in its top left corner
+    // and the readout and plot panel a square in its bottom right
+    let mut window_size: (i32, i32) = (wsize_x as i32, wsize_y as i32);
+    let mut panel_size: i32 = cmp::min(window_size.0, window_size.1);
 
     let _gl_context = window.gl_create_context().unwrap();
     let gl =
@@ -102,24 +134,52 @@ fn main() {
     }
 
     unsafe {
-        gl.Viewport(0, 0, 500, 500);
+        gl.Viewport(0, 0, window_size.0, window_size.1);
         gl.ClearColor(1.0, 1.0, 1.0, 1.0);
         gl.PointSize(7.0);
     }
 
     // setup simulator
     let sim = Rc::new(RefCell::new(Simulator::new(1.0 / 120.0)));
-
-    for i in -20..20 {
-        for j in -20..20 {
-            sim.borrow_mut().add_particle(i as f32, j as f32, 0.0, 0.0);
-        }
-    }
+    // the default grid is too large to sum interactions directly
+    sim.borrow_mut().set_solver(Solver::BarnesHut { theta: 0.5 });
 
     let mut current_buf_size = vertices.len();
     let mut pause = true;
 
     let mut window_info = window::WindowData::new(&gl, 30.0, 30.0, sim.clone());
+    window_info.screen_size = (window_size.0 as f32, window_size.1 as f32);
+    sim.borrow_mut().set_boundary(Boundary::centered(
+        window_info.x_range,
+        window_info.y_range,
+        BoundaryMode::Open,
+    ));
+
+    // start from a scene file if one was given, otherwise the default grid
+    let args: Vec<String> = std::env::args().collect();
+    let scene_arg = args
+        .iter()
+        .position(|a| a == "--scene")
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+    let scene_path = scene_arg.clone().unwrap_or_else(|| String::from("scene.json"));
+    match scene_arg {
+        Some(path) => {
+            if let Err(e) = sim.borrow_mut().load_scene(&path) {
+                eprintln!("Could not load scene: {}", e);
+                std::process::exit(1);
+            }
+        }
+        None => {
+            for i in -20..20 {
+                for j in -20..20 {
+                    sim.borrow_mut()
+                        .add_particle(i as f32, j as f32, 0.0, 0.0, 1.0, 1.0)
+                        .unwrap();
+                }
+            }
+        }
+    }
     let play_button = Rc::new(RefCell::new(input::Button::new(
         &gl,
         -0.95,
@@ -225,6 +285,22 @@ fn main() {
         0.06,
         "force_Harmonic".to_string(),
     )));
+    let driven_harmonic_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.36,
+        0.07,
+        0.36,
+        0.06,
+        "force_DrivenHarmonic".to_string(),
+    )));
+    let rotating_dipole_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.36,
+        -0.01,
+        0.36,
+        0.06,
+        "force_RotatingDipole".to_string(),
+    )));
     let no_force_button = Rc::new(RefCell::new(input::Button::new(
         &gl,
         -0.95,
@@ -233,32 +309,186 @@ fn main() {
         0.06,
         "force_NoForce".to_string(),
     )));
-    let bounce_button = Rc::new(RefCell::new(input::Button::new(
+    let boundary_button = Rc::new(RefCell::new(input::Button::new(
         &gl,
         -0.95 + 0.20,
         0.87,
         0.32,
         0.06,
-        "toggle_bounce".to_string(),
-    )));
-    let mut bounce_string = String::from("Enable Bounce");
-
-    let mut buttons_vec: Vec<Rc<RefCell<input::Button>>> = Vec::new();
-    buttons_vec.push(play_button);
-    buttons_vec.push(pause_button);
-    buttons_vec.push(clear_button);
-    buttons_vec.push(grid_button);
-    buttons_vec.push(gravity_button);
-    buttons_vec.push(gravity_resistive_button);
-    buttons_vec.push(butterfly_button);
-    buttons_vec.push(windows_xp_button);
-    buttons_vec.push(logistic_button);
-    buttons_vec.push(bounce_button);
-    buttons_vec.push(inverse_square_button);
-    buttons_vec.push(parallel_electric_button);
-    buttons_vec.push(harmonic_button);
-    buttons_vec.push(sine_button);
-    buttons_vec.push(no_force_button);
+        "cycle_boundary".to_string(),
+    )));
+    let collision_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.56,
+        0.87,
+        0.40,
+        0.06,
+        "toggle_collisions".to_string(),
+    )));
+    let particle_preset_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.20,
+        0.63,
+        0.32,
+        0.06,
+        "cycle_particle_preset".to_string(),
+    )));
+    let mut particle_preset = 0;
+    let place_tool_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.36,
+        0.47,
+        0.36,
+        0.06,
+        "cycle_place_tool".to_string(),
+    )));
+    let mut place_tool = 0;
+    // clicking a particle in grab mode pulls it towards the cursor until released
+    let grab_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.76,
+        0.47,
+        0.28,
+        0.06,
+        "toggle_grab".to_string(),
+    )));
+    let mut grab_mode = false;
+    let expression_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95,
+        -0.17,
+        1.4,
+        0.06,
+        "edit_expression".to_string(),
+    )));
+    let mut editing_expression = false;
+    let mut expression_text = String::from("k = 10; ax = -k*x; ay = -k*y + 5*sin(t)");
+    let mut expression_error = String::new();
+    let save_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95,
+        -0.09,
+        0.16,
+        0.06,
+        "save_scene".to_string(),
+    )));
+    let load_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.20,
+        -0.09,
+        0.16,
+        0.06,
+        "load_scene".to_string(),
+    )));
+    let delete_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.44,
+        -0.09,
+        0.20,
+        0.06,
+        "delete_selected".to_string(),
+    )));
+    let freeze_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.68,
+        -0.09,
+        0.24,
+        0.06,
+        "freeze_selected".to_string(),
+    )));
+    // ids of the selected particles, the last one is shown in the info box
+    let mut selected: Vec<u32> = Vec::new();
+    let solver_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.76,
+        0.71,
+        0.32,
+        0.06,
+        "toggle_solver".to_string(),
+    )));
+    let interaction_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.40,
+        0.71,
+        0.32,
+        0.06,
+        "cycle_interaction".to_string(),
+    )));
+    let integrator_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.20,
+        0.79,
+        0.32,
+        0.06,
+        "cycle_integrator".to_string(),
+    )));
+    let view_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.56,
+        0.79,
+        0.36,
+        0.06,
+        "cycle_view".to_string(),
+    )));
+    let trail_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.56,
+        0.63,
+        0.32,
+        0.06,
+        "cycle_trails".to_string(),
+    )));
+    let plot_button = Rc::new(RefCell::new(input::Button::new(
+        &gl,
+        -0.95 + 0.52,
+        0.55,
+        0.48,
+        0.06,
+        "cycle_plot".to_string(),
+    )));
+    // a graph of the chosen quantity over the last 10 seconds, hidden when None
+    let mut plot_panel = plot::Plot::new(&gl, 0.25, -0.55, 0.70, 0.38, 10.0);
+    let mut plot_quantity: Option<usize> = Some(0);
+
+    let buttons_vec: Vec<Rc<RefCell<input::Button>>> = vec![
+        play_button,
+        pause_button,
+        clear_button,
+        grid_button,
+        gravity_button,
+        gravity_resistive_button,
+        butterfly_button,
+        windows_xp_button,
+        logistic_button,
+        boundary_button,
+        inverse_square_button,
+        parallel_electric_button,
+        harmonic_button,
+        driven_harmonic_button,
+        rotating_dipole_button,
+        sine_button,
+        no_force_button,
+        integrator_button,
+        particle_preset_button,
+        place_tool_button,
+        grab_button,
+        interaction_button,
+        solver_button,
+        collision_button,
+        save_button,
+        load_button,
+        expression_button,
+        plot_button,
+        view_button,
+        trail_button,
+        delete_button,
+        freeze_button,
+    ];
+
+    // timestep and restitution, then the weight and constants of each enabled force
+    // source, laid out again whenever that list changes
+    let mut sliders: Vec<input::Slider> = Vec::new();
+    let mut dragging_slider: Option<usize> = None;
 
     // setup freetype
 
@@ -284,31 +514,56 @@ fn main() {
 
     let mut event_pump = sdl.event_pump().unwrap();
     let mut creating_particle = false;
+    let mut dragging_source: Option<usize> = None;
+    let mut line_start: Option<PhysVector> = None;
     let mut particle_start = PhysVector { x: 0.0, y: 0.0 };
+    let mut panning: Option<(i32, i32)> = None;
+    let mut pan_start = (0, 0);
+    let mut mouse_position = (0, 0);
     'main: loop {
         for event in event_pump.poll_iter() {
+            if let sdl2::event::Event::MouseMotion { x, y, .. } = event {
+                mouse_position = (x, y);
+            }
             match event {
                 sdl2::event::Event::Quit { .. } => break 'main,
-                sdl2::event::Event::Window {
-                    timestamp: _,
-                    window_id: _,
-                    win_event,
-                } => match win_event {
-                    sdl2::event::WindowEvent::Resized {
-                        0: found_x,
-                        1: found_y,
-                    } => {
-                        x = found_x;
-                        y = found_y;
-                        width = cmp::min(x, y);
-                        x_offset = (x - width) / 2;
-                        y_offset = (y - width) / 2;
-                        unsafe {
-                            gl.Viewport(x_offset, y_offset, width, width);
+                sdl2::event::Event::TextInput { text, .. } if editing_expression => {
+                    // the glyph cache is indexed by byte, keep to ASCII
+                    expression_text.extend(text.chars().filter(|c| c.is_ascii() && !c.is_control()));
+                }
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(key), ..
+                } if editing_expression => match key {
+                    sdl2::keyboard::Keycode::Backspace => {
+                        expression_text.pop();
+                    }
+                    sdl2::keyboard::Keycode::Return | sdl2::keyboard::Keycode::KpEnter => {
+                        match FieldExpression::parse(&expression_text) {
+                            Ok(expression) => {
+                                sim.borrow_mut().add_force(simulator::ForceSource::new(
+                                    simulator::SimulateFunction::Expression(expression),
+                                ));
+                                expression_error.clear();
+                                editing_expression = false;
+                                video_subsystem.text_input().stop();
+                            }
+                            Err(e) => expression_error = e,
                         }
                     }
+                    sdl2::keyboard::Keycode::Escape => {
+                        editing_expression = false;
+                        video_subsystem.text_input().stop();
+                    }
                     _ => {}
                 },
+                sdl2::event::Event::Window {
+                    win_event: sdl2::event::WindowEvent::Resized(found_x, found_y),
+                    ..
+                } => {
+                    window_size = (found_x, found_y);
+                    panel_size = cmp::min(found_x, found_y);
+                    window_info.screen_size = (found_x as f32, found_y as f32);
+                }
                 sdl2::event::Event::MouseButtonDown {
                     timestamp: _ts,
                     window_id: _wi,
@@ -318,11 +573,20 @@ fn main() {
                     x,
                     y,
                 } => {
-                    let c_x = ((x - x_offset) as f32 * 2.0 / width as f32) - 1.0;
-                    let c_y = (((y - y_offset) as f32 * 2.0 / width as f32) - 1.0) * -1.0;
+                    let (c_x, c_y) = panel_point(x, y, panel_size);
 
                     let mut button_found = false;
 
+                    for (i, slider) in sliders.iter().enumerate() {
+                        if !slider.parameter().is_empty() && slider.contains(c_x, c_y) {
+                            button_found = true;
+                            dragging_slider = Some(i);
+                        }
+                    }
+                    if let Some(i) = dragging_slider {
+                        set_from_slider(&mut sim.borrow_mut(), &sliders[i], c_x);
+                    }
+
                     for b in &buttons_vec {
                         let b_x = b.borrow().x;
                         let b_y = b.borrow().y;
@@ -344,7 +608,7 @@ fn main() {
                                     let mut s = sim.borrow_mut();
                                     for i in -20..20 {
                                         for j in -20..20 {
-                                            s.add_particle(i as f32, j as f32, 0.0, 0.0);
+                                            s.add_particle(i as f32, j as f32, 0.0, 0.0, 1.0, 1.0).unwrap();
                                         }
                                     }
                                 }
@@ -353,81 +617,311 @@ fn main() {
                                     let n = 100.0;
                                     for x in -n as isize..n as isize {
                                         let theta = ((x as f32)/n) * 2.0 * PI;
-                                        s.add_particle(x as f32 / (n / 30.0), theta.sin() * 10.0, 0.0, 10.0_f32.sqrt() * 10.0 * theta.cos());
+                                        s.add_particle(x as f32 / (n / 30.0), theta.sin() * 10.0, 0.0, 10.0_f32.sqrt() * 10.0 * theta.cos(), 1.0, 1.0).unwrap();
+                                    }
+                                }
+                                "force_NoForce" => {
+                                    let mut s = sim.borrow_mut();
+                                    let names: Vec<&str> = s.forces().iter().map(|f| f.name()).collect();
+                                    for name in names {
+                                        s.set_force_enabled(name, false).unwrap();
                                     }
                                 }
-                                "force_Gravity" => {
-                                    sim.borrow_mut()
-                                        .set_function(simulator::SimulateFunction::Gravity);
-                                    window_info.vectors_require_update = true;
+                                name if name.starts_with("force_") => {
+                                    if let Err(e) = sim.borrow_mut().toggle_force(&name["force_".len()..]) {
+                                        println!("{}", e);
+                                    }
                                 }
-                                "force_GravityResistive" => {
-                                    sim.borrow_mut().set_function(
-                                        simulator::SimulateFunction::GravityResistive,
-                                    );
-                                    window_info.vectors_require_update = true;
+                                "cycle_boundary" => sim.borrow_mut().cycle_boundary_mode(),
+                                "cycle_integrator" => {
+                                    let mut s = sim.borrow_mut();
+                                    let names = &integrator::INTEGRATOR_NAMES;
+                                    let current = names
+                                        .iter()
+                                        .position(|n| *n == s.integrator().name())
+                                        .unwrap_or(0);
+                                    let next = names[(current + 1) % names.len()];
+                                    s.set_integrator(integrator::integrator_from_name(next).unwrap());
                                 }
-                                "force_Butterfly" => {
-                                    sim.borrow_mut()
-                                        .set_function(simulator::SimulateFunction::Butterfly);
-                                    window_info.vectors_require_update = true;
+                                "cycle_place_tool" => {
+                                    place_tool = (place_tool + 1) % PLACE_TOOLS.len();
                                 }
-                                "force_WindowsXP" => {
-                                    sim.borrow_mut()
-                                        .set_function(simulator::SimulateFunction::WindowsXP);
-                                    window_info.vectors_require_update = true;
+                                "toggle_grab" => {
+                                    grab_mode = !grab_mode;
                                 }
-                                "force_Logistic" => {
-                                    sim.borrow_mut()
-                                        .set_function(simulator::SimulateFunction::Logistic);
-                                    window_info.vectors_require_update = true;
+                                "cycle_view" => {
+                                    let next = match window_info.view_mode {
+                                        window::ViewMode::Space => window::ViewMode::PhaseY,
+                                        window::ViewMode::PhaseY => window::ViewMode::PhaseX,
+                                        window::ViewMode::PhaseX => window::ViewMode::Space,
+                                    };
+                                    window_info.set_view_mode(next);
                                 }
-                                "force_InverseSquare" => {
-                                    sim.borrow_mut()
-                                        .set_function(simulator::SimulateFunction::InverseSquare);
-                                    window_info.vectors_require_update = true;
+                                "delete_selected" => {
+                                    sim.borrow_mut().remove_particles(&selected);
+                                    selected.clear();
                                 }
-                                "force_NoForce" => {
-                                    sim.borrow_mut()
-                                        .set_function(simulator::SimulateFunction::NoForce);
-                                    window_info.vectors_require_update = true;
+                                "freeze_selected" => {
+                                    let mut s = sim.borrow_mut();
+                                    let all_frozen = selected
+                                        .iter()
+                                        .all(|id| s.particle(*id).is_some_and(|p| p.is_frozen()));
+                                    s.set_frozen(&selected, !all_frozen);
                                 }
-                                "force_ParallelElectric" => {
-                                    sim.borrow_mut()
-                                        .set_function(simulator::SimulateFunction::ParallelElectric);
-                                    window_info.vectors_require_update = true;
+                                "cycle_trails" => {
+                                    let current = TRAIL_LENGTHS
+                                        .iter()
+                                        .position(|l| *l == window_info.trail_length)
+                                        .unwrap_or(0);
+                                    window_info.trail_length =
+                                        TRAIL_LENGTHS[(current + 1) % TRAIL_LENGTHS.len()];
                                 }
-                                "force_Harmonic" => {
-                                    sim.borrow_mut()
-                                        .set_function(simulator::SimulateFunction::Harmonic);
-                                    window_info.vectors_require_update = true;
+                                "cycle_plot" => {
+                                    plot_quantity = match plot_quantity {
+                                        Some(i) if i + 1 < plot::PLOT_QUANTITIES.len() => Some(i + 1),
+                                        Some(_) => None,
+                                        None => Some(0),
+                                    };
+                                    if let Some(i) = plot_quantity {
+                                        plot_panel.set_quantity(plot::PLOT_QUANTITIES[i]);
+                                    }
+                                }
+                                "cycle_particle_preset" => {
+                                    particle_preset = (particle_preset + 1) % PARTICLE_PRESETS.len();
+                                }
+                                "cycle_interaction" => {
+                                    let mut s = sim.borrow_mut();
+                                    let next = match s.interaction() {
+                                        Interaction::None => Interaction::default_gravity(),
+                                        Interaction::Gravity { .. } => Interaction::default_coulomb(),
+                                        Interaction::Coulomb { .. } => Interaction::None,
+                                    };
+                                    s.set_interaction(next);
                                 }
-                                "toggle_bounce" => {
-                                    let current;
-                                    {
-                                        current = sim.borrow().bounce_off_walls;
+                                "toggle_solver" => {
+                                    let mut s = sim.borrow_mut();
+                                    let next = match s.solver() {
+                                        Solver::Direct => Solver::BarnesHut { theta: 0.5 },
+                                        Solver::BarnesHut { .. } => Solver::Direct,
+                                    };
+                                    s.set_solver(next);
+                                }
+                                "toggle_collisions" => {
+                                    let mut s = sim.borrow_mut();
+                                    s.collide_particles = !s.collide_particles;
+                                }
+                                "save_scene" => {
+                                    match sim.borrow().save_scene(&scene_path) {
+                                        Ok(()) => println!("Saved scene to {}", scene_path),
+                                        Err(e) => println!("Could not save scene: {}", e),
                                     }
-                                    sim.borrow_mut().bounce_off_walls = !current;
-                                    if sim.borrow().bounce_off_walls {
-                                        bounce_string = String::from("Disable Bounce");
-                                    } else {
-                                        bounce_string = String::from("Enable Bounce");
+                                }
+                                "load_scene" => {
+                                    let loaded = sim.borrow_mut().load_scene(&scene_path);
+                                    if let Err(e) = loaded {
+                                        println!("Could not load scene: {}", e);
                                     }
                                 }
+                                "edit_expression" => {
+                                    editing_expression = true;
+                                    video_subsystem.text_input().start();
+                                }
                                 _ => {
                                     println!("Unknown function");
                                 }
                             }
                         }
                     }
-                    if !button_found {
-                        creating_particle = true;
-                        particle_start = PhysVector {
-                            x: (c_x * window_info.x_range).into(),
-                            y: (c_y * window_info.y_range).into(),
-                        };
+                    // phase space has no world position to put things at
+                    if !button_found && window_info.view_mode == window::ViewMode::Space {
+                        let world = world_at(&window_info, x, y);
+                        let shift = sdl
+                            .keyboard()
+                            .mod_state()
+                            .intersects(sdl2::keyboard::Mod::LSHIFTMOD | sdl2::keyboard::Mod::RSHIFTMOD);
+                        let mut s = sim.borrow_mut();
+                        dragging_source = s.source_near(world.x, world.y, SOURCE_PICK_RADIUS);
+                        let picked = s.particle_near(
+                            world.x,
+                            world.y,
+                            PARTICLE_PICK_RADIUS / window_info.zoom,
+                        );
+                        if dragging_source.is_some() {
+                            // grabbing a source takes priority over selecting
+                        } else if let Some(id) = picked {
+                            // shift adds to or takes away from the selection
+                            if !shift {
+                                selected.clear();
+                                selected.push(id);
+                            } else if let Some(i) = selected.iter().position(|s| *s == id) {
+                                selected.remove(i);
+                            } else {
+                                selected.push(id);
+                            }
+                            if grab_mode {
+                                s.grab(id, world.x, world.y);
+                            }
+                        } else {
+                            if !shift {
+                                selected.clear();
+                            }
+                            match PLACE_TOOLS[place_tool] {
+                                "+ Charge" => {
+                                    s.add_source(FieldSource::PointCharge {
+                                        pos: world,
+                                        charge: 200.0,
+                                    });
+                                }
+                                "- Charge" => {
+                                    s.add_source(FieldSource::PointCharge {
+                                        pos: world,
+                                        charge: -200.0,
+                                    });
+                                }
+                                "Mass" => {
+                                    s.add_source(FieldSource::PointMass {
+                                        pos: world,
+                                        mass: 500.0,
+                                    });
+                                }
+                                // these are sized by dragging
+                                "Line Charge" | "Emitter" | "Line Emitter" | "Sink" => {
+                                    line_start = Some(world)
+                                }
+                                _ => {
+                                    creating_particle = true;
+                                    particle_start = world;
+                                }
+                            }
+                        }
+                    }
+                }
+                sdl2::event::Event::MouseButtonDown {
+                    mouse_btn: sdl2::mouse::MouseButton::Right,
+                    x,
+                    y,
+                    ..
+                } => {
+                    panning = Some((x, y));
+                    pan_start = (x, y);
+                }
+                sdl2::event::Event::MouseMotion { x, y, .. } if panning.is_some() => {
+                    let (last_x, last_y) = panning.unwrap();
+                    window_info.pan(
+                        (x - last_x) as f32 * 2.0 / window_size.0 as f32,
+                        -(y - last_y) as f32 * 2.0 / window_size.1 as f32,
+                    );
+                    panning = Some((x, y));
+                }
+                sdl2::event::Event::MouseButtonUp {
+                    mouse_btn: sdl2::mouse::MouseButton::Right,
+                    x,
+                    y,
+                    ..
+                } => {
+                    panning = None;
+                    // a right click that didn't drag deletes the source under it
+                    let moved = (x - pan_start.0).abs().max((y - pan_start.1).abs());
+                    if moved <= PAN_THRESHOLD && window_info.view_mode == window::ViewMode::Space {
+                        let world = world_at(&window_info, x, y);
+                        let mut s = sim.borrow_mut();
+                        if let Some(i) = s.source_near(world.x, world.y, SOURCE_PICK_RADIUS) {
+                            s.remove_source(i);
+                        } else if let Some(i) = s.emitter_near(world.x, world.y, SOURCE_PICK_RADIUS) {
+                            s.remove_emitter(i);
+                        } else if let Some(i) = s.sink_near(world.x, world.y, 0.0) {
+                            s.remove_sink(i);
+                        }
+                    }
+                }
+                sdl2::event::Event::MouseWheel { y: scroll, .. } => {
+                    let (c_x, c_y) = window_info.pixel_to_screen(mouse_position.0, mouse_position.1);
+                    window_info.zoom_at(c_x, c_y, ZOOM_STEP.powi(scroll));
+                }
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(sdl2::keyboard::Keycode::Home),
+                    ..
+                } => {
+                    window_info.reset_camera();
+                }
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(sdl2::keyboard::Keycode::Delete),
+                    ..
+                } => {
+                    sim.borrow_mut().remove_particles(&selected);
+                    selected.clear();
+                }
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(sdl2::keyboard::Keycode::Escape),
+                    ..
+                } => {
+                    selected.clear();
+                }
+                sdl2::event::Event::MouseMotion { x, y, .. } if sim.borrow().joint().is_some() => {
+                    let world = world_at(&window_info, x, y);
+                    sim.borrow_mut().move_grab(world.x, world.y);
+                }
+                sdl2::event::Event::MouseButtonUp {
+                    mouse_btn: sdl2::mouse::MouseButton::Left,
+                    ..
+                } if sim.borrow().joint().is_some() => {
+                    sim.borrow_mut().release();
+                }
+                sdl2::event::Event::MouseMotion { x, y, .. } if dragging_source.is_some() => {
+                    let world = world_at(&window_info, x, y);
+                    sim.borrow_mut()
+                        .move_source(dragging_source.unwrap(), world.x, world.y);
+                }
+                sdl2::event::Event::MouseButtonUp {
+                    mouse_btn: sdl2::mouse::MouseButton::Left,
+                    ..
+                } if dragging_source.is_some() => {
+                    dragging_source = None;
+                }
+                sdl2::event::Event::MouseButtonUp {
+                    mouse_btn: sdl2::mouse::MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } if line_start.is_some() => {
+                    let start = line_start.take().unwrap();
+                    let end = world_at(&window_info, x, y);
+                    let (dx, dy) = (end.x - start.x, end.y - start.y);
+                    let length = dx.hypot(dy);
+                    let mut s = sim.borrow_mut();
+                    match PLACE_TOOLS[place_tool] {
+                        // the drag is the launch velocity, like placing a particle
+                        "Emitter" => {
+                            s.add_emitter(Emitter::point(start, dy.atan2(dx), length)).unwrap();
+                        }
+                        "Sink" => {
+                            s.add_sink(Sink::circle(start, length.max(SOURCE_PICK_RADIUS)));
+                        }
+                        // a click without a drag has nothing to spread the line over
+                        _ if length <= SOURCE_PICK_RADIUS => {}
+                        "Line Emitter" => {
+                            s.add_emitter(Emitter::line(start, end, LINE_EMITTER_SPEED)).unwrap();
+                        }
+                        _ => {
+                            s.add_source(FieldSource::LineCharge {
+                                start,
+                                end,
+                                density: 20.0,
+                            });
+                        }
                     }
                 }
+                sdl2::event::Event::MouseMotion { x, .. } if dragging_slider.is_some() => {
+                    let (c_x, _) = panel_point(x, 0, panel_size);
+                    let slider = &sliders[dragging_slider.unwrap()];
+                    set_from_slider(&mut sim.borrow_mut(), slider, c_x);
+                }
+                sdl2::event::Event::MouseButtonUp {
+                    mouse_btn: sdl2::mouse::MouseButton::Left,
+                    ..
+                } if dragging_slider.is_some() => {
+                    dragging_slider = None;
+                }
                 sdl2::event::Event::MouseButtonUp {
                     timestamp: _ts,
                     window_id: _wi,
@@ -436,32 +930,39 @@ fn main() {
                     clicks: _cl,
                     x,
                     y,
-                } => {
-                    if creating_particle {
-                        let c_x = ((x - x_offset) as f32 * 2.0 / width as f32) - 1.0;
-                        let c_y = (((y - y_offset) as f32 * 2.0 / width as f32) - 1.0) * -1.0;
-                        let particle_end = PhysVector {
-                            x: (c_x * window_info.x_range).into(),
-                            y: (c_y * window_info.y_range).into(),
-                        };
-                        let vel = &particle_end + &(&particle_start * -1.0);
-                        sim.borrow_mut().add_particle(
-                            particle_start.x,
-                            particle_start.y,
-                            vel.x,
-                            vel.y,
-                        );
-                        creating_particle = false;
+                } if creating_particle => {
+                    let particle_end = world_at(&window_info, x, y);
+                    let vel = &particle_end + &(&particle_start * -1.0);
+                    let (_, mass, charge) = PARTICLE_PRESETS[particle_preset];
+                    let added = sim.borrow_mut().add_particle(
+                        particle_start.x,
+                        particle_start.y,
+                        vel.x,
+                        vel.y,
+                        mass,
+                        charge,
+                    );
+                    if let Err(e) = added {
+                        println!("Could not add particle: {}", e);
                     }
+                    creating_particle = false;
                 }
                 _ => {}
             }
         }
+        if window_info.view_mode != window::ViewMode::Space {
+            window_info.fit_velocity_range();
+        }
         unsafe {
+            gl.Viewport(0, 0, window_size.0, window_size.1);
             gl.Clear(gl::COLOR_BUFFER_BIT);
-            current_buf_size =
-                sim.borrow()
-                    .draw_particles(&vbo, &vao, &mut current_buf_size, &window_info);
+            current_buf_size = gl_draw::draw_particles(
+                &sim.borrow(),
+                &vbo,
+                &vao,
+                &mut current_buf_size,
+                &window_info,
+            );
         }
 
         if !pause {
@@ -469,9 +970,16 @@ fn main() {
         }
 
         gl_draw::draw_gridlines(&mut window_info);
-        Simulator::draw_vectors(&mut window_info);
+        if window_info.view_mode == window::ViewMode::Space {
+            gl_draw::draw_vectors(&mut window_info);
+            gl_draw::draw_sources(&mut window_info);
+            gl_draw::draw_flow(&mut window_info);
+        }
+        gl_draw::update_trails(&mut window_info);
+        gl_draw::draw_trails(&mut window_info);
 
         program.set_used();
+        gl_draw::set_camera(&window_info, &program);
         unsafe {
             gl.BindVertexArray(vao);
             gl.BindBuffer(gl::ARRAY_BUFFER, vbo);
@@ -482,17 +990,76 @@ fn main() {
             // gl::BindBuffer(gl::ARRAY_BUFFER, 0);
             // gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
         }
+        // forget particles that were absorbed, cleared or replaced by a scene
+        selected.retain(|id| sim.borrow().particle(*id).is_some());
+        gl_draw::draw_selection(&mut window_info, &selected);
+        gl_draw::draw_joint(&mut window_info);
         // pause_button.borrow_mut().draw();
         // play_button.borrow_mut().draw();
         // grid_button.borrow_mut().draw();
         // clear_button.borrow_mut().draw();
 
+        unsafe {
+            gl.Viewport(0, window_size.1 - panel_size, panel_size, panel_size);
+        }
         for b in &buttons_vec {
-            b.borrow_mut().draw();
+            let mut b = b.borrow_mut();
+            if let Some(name) = b.onclick.strip_prefix("force_") {
+                b.active = sim.borrow().force_enabled(name);
+            }
+            if b.onclick == "edit_expression" {
+                b.active = sim.borrow().force_enabled("Expression");
+            }
+            b.draw();
+        }
+
+        // rebind the sliders in case sources were toggled
+        let mut parameter_names = vec![String::from("timestep"), String::from("restitution")];
+        parameter_names.extend(
+            sim.borrow()
+                .forces()
+                .iter()
+                .filter(|s| s.enabled)
+                .flat_map(|s| s.parameters())
+                .map(|p| p.name),
+        );
+        let bound: Vec<&str> = sliders
+            .iter()
+            .map(|s| s.parameter())
+            .filter(|p| !p.is_empty())
+            .collect();
+        if bound != parameter_names {
+            layout_sliders(&gl, &mut sliders, parameter_names);
+        }
+        let slider_parameters: Vec<_> = sliders
+            .iter()
+            .map(|s| sim.borrow().parameter(s.parameter()))
+            .collect();
+        for (slider, parameter) in sliders.iter_mut().zip(slider_parameters.iter()) {
+            if let Some(p) = parameter {
+                slider.draw((p.value - p.min) / (p.max - p.min));
+            }
+        }
+
+        let diagnostics = sim.borrow().diagnostics();
+        if plot_quantity.is_some() {
+            // follow the selected particle, otherwise the first one until the tracked
+            // one disappears
+            let tracked = plot_panel.particle.filter(|id| {
+                sim.borrow().particle_list.borrow().iter().any(|p| p.get_id() == *id)
+            });
+            let first = sim.borrow().particle_list.borrow().first().map(|p| p.get_id());
+            plot_panel.set_particle(selected.last().cloned().or(tracked).or(first));
+            plot_panel.sample(&sim.borrow(), &diagnostics);
+            unsafe {
+                gl.Viewport(window_size.0 - panel_size, 0, panel_size, panel_size);
+            }
+            plot_panel.draw();
         }
 
         // render text
         unsafe {
+            gl.Viewport(0, window_size.1 - panel_size, panel_size, panel_size);
             gl.Enable(gl::BLEND);
             gl.Enable(gl::POLYGON_SMOOTH);
             gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
@@ -503,7 +1070,7 @@ fn main() {
             gl.BufferData(
                 gl::ARRAY_BUFFER,
                 (std::mem::size_of::<f32>() * 6 * 4) as isize,
-                0 as *const gl::types::GLvoid,
+                std::ptr::null::<gl::types::GLvoid>(),
                 gl::DYNAMIC_DRAW,
             );
             gl.EnableVertexAttribArray(0);
@@ -701,6 +1268,58 @@ fn main() {
             0.25,
         );
 
+        gl_draw::render_text(
+            &gl,
+            &format!("Place: {}", PLACE_TOOLS[place_tool]),
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            174.0,
+            428.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            if grab_mode { "Grab: On" } else { "Grab: Off" },
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            334.0,
+            428.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            "Driven Harmonic",
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            174.0,
+            308.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            "Rotating Dipole",
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            174.0,
+            286.0,
+            0.25,
+        );
+
         gl_draw::render_text(
             &gl,
             "No Force",
@@ -716,17 +1335,193 @@ fn main() {
 
         gl_draw::render_text(
             &gl,
-            &bounce_string,
+            &format!("Walls: {}", sim.borrow().boundary().mode.name()),
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            110.0,
+            548.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            &format!("New: {}", PARTICLE_PRESETS[particle_preset].0),
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            110.0,
+            476.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            sim.borrow().interaction().name(),
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            190.0,
+            500.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            sim.borrow().solver().name(),
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            334.0,
+            500.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            sim.borrow().integrator().name(),
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            110.0,
+            524.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            &format!(
+                "f: {}{}",
+                expression_text,
+                if editing_expression { "_" } else { "" }
+            ),
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            30.0,
+            236.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            &expression_error,
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            30.0,
+            212.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            "Save",
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            30.0,
+            260.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            "Load",
             &ft_face,
             &ft_program,
             &mut ft_vao,
             &mut ft_vbo,
             &mut window_info,
             110.0,
+            260.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            "Delete",
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            206.0,
+            260.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            if !selected.is_empty()
+                && selected
+                    .iter()
+                    .all(|id| sim.borrow().particle(*id).is_some_and(|p| p.is_frozen()))
+            {
+                "Unfreeze"
+            } else {
+                "Freeze"
+            },
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            302.0,
+            260.0,
+            0.25,
+        );
+
+        gl_draw::render_text(
+            &gl,
+            if sim.borrow().collide_particles {
+                "Disable Collisions"
+            } else {
+                "Enable Collisions"
+            },
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            254.0,
             548.0,
             0.25,
         );
 
+        for (slider, parameter) in sliders.iter().zip(slider_parameters.iter()) {
+            if let Some(p) = parameter {
+                gl_draw::render_text(
+                    &gl,
+                    &format!("{} {:.4}", p.name, p.value),
+                    &ft_face,
+                    &ft_program,
+                    &mut ft_vao,
+                    &mut ft_vbo,
+                    &mut window_info,
+                    30.0,
+                    300.0 * slider.y + 287.0,
+                    0.25,
+                );
+            }
+        }
+
         gl_draw::render_text(
             &gl,
             &format!("Time: {:.*}", 1, sim.borrow().time()),
@@ -740,6 +1535,226 @@ fn main() {
             0.5,
         );
 
+        gl_draw::render_text(
+            &gl,
+            &if window_info.trail_length == 0 {
+                String::from("Trails: Off")
+            } else {
+                format!("Trails: {}", window_info.trail_length)
+            },
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            254.0,
+            476.0,
+            0.25,
+        );
+        gl_draw::render_text(
+            &gl,
+            &format!("View: {}", window_info.view_mode.name()),
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            254.0,
+            524.0,
+            0.25,
+        );
+        gl_draw::render_text(
+            &gl,
+            &match plot_quantity {
+                Some(_) if plot_panel.follows_particle() => match plot_panel.particle {
+                    Some(id) => format!("Plot: {} #{}", plot_panel.quantity.name(), id),
+                    None => format!("Plot: {}", plot_panel.quantity.name()),
+                },
+                Some(_) => format!("Plot: {}", plot_panel.quantity.name()),
+                None => String::from("Plot: Off"),
+            },
+            &ft_face,
+            &ft_program,
+            &mut ft_vao,
+            &mut ft_vbo,
+            &mut window_info,
+            238.0,
+            452.0,
+            0.25,
+        );
+
+        // the readout and plot labels belong to the bottom right panel
+        unsafe {
+            gl.Viewport(window_size.0 - panel_size, 0, panel_size, panel_size);
+        }
+        // conserved quantities, top right
+        let mut readout = vec![
+            format!("KE {:.1}", diagnostics.kinetic_energy),
+            match diagnostics.potential_energy {
+                Some(u) => format!("PE {:.1}", u),
+                None => String::from("PE n/a"),
+            },
+            match diagnostics.energy_drift {
+                Some(drift) => format!("drift {:+.3}%", drift * 100.0),
+                None => String::from("drift n/a"),
+            },
+            format!(
+                "p ({:.1}, {:.1})",
+                diagnostics.momentum.x, diagnostics.momentum.y
+            ),
+            format!("L {:.1}", diagnostics.angular_momentum),
+        ];
+        if !sim.borrow().sinks().is_empty() {
+            let absorbed: u64 = sim.borrow().sinks().iter().map(|s| s.absorbed).sum();
+            readout.push(format!("absorbed {}", absorbed));
+        }
+        for (i, line) in readout.iter().enumerate() {
+            gl_draw::render_text(
+                &gl,
+                line,
+                &ft_face,
+                &ft_program,
+                &mut ft_vao,
+                &mut ft_vbo,
+                &mut window_info,
+                640.0,
+                566.0 - 20.0 * i as f32,
+                0.25,
+            );
+        }
+
+        // the most recently selected particle, under the readout
+        let inspected = selected.last().and_then(|id| sim.borrow().particle(*id));
+        if let Some(p) = inspected {
+            let pos = p.get_pos();
+            let vel = p.get_vel();
+            let acc = sim.borrow().acceleration_for(&p, sim.borrow().time());
+            let mut info = Vec::new();
+            if selected.len() > 1 {
+                info.push(format!("{} selected", selected.len()));
+            }
+            info.push(format!(
+                "Particle #{}{}",
+                p.get_id(),
+                if p.is_frozen() { " (frozen)" } else { "" }
+            ));
+            info.push(format!("pos ({:.2}, {:.2})", pos.x, pos.y));
+            info.push(format!("vel ({:.2}, {:.2})", vel.x, vel.y));
+            info.push(format!("speed {:.2}", vel.x.hypot(vel.y)));
+            info.push(format!("acc ({:.2}, {:.2})", acc.x, acc.y));
+            for (i, line) in info.iter().enumerate() {
+                gl_draw::render_text(
+                    &gl,
+                    line,
+                    &ft_face,
+                    &ft_program,
+                    &mut ft_vao,
+                    &mut ft_vbo,
+                    &mut window_info,
+                    640.0,
+                    450.0 - 20.0 * i as f32,
+                    0.25,
+                );
+            }
+        }
+        if plot_quantity.is_some() {
+            for (text, label_x, label_y) in plot_panel.labels() {
+                gl_draw::render_text(
+                    &gl,
+                    &text,
+                    &ft_face,
+                    &ft_program,
+                    &mut ft_vao,
+                    &mut ft_vbo,
+                    &mut window_info,
+                    400.0 * label_x + 410.0,
+                    300.0 * label_y + 287.0,
+                    0.25,
+                );
+            }
+        }
+
+        // the phase space axis labels sit at the edges of the whole view
+        unsafe {
+            gl.Viewport(0, 0, window_size.0, window_size.1);
+        }
+        if window_info.view_mode != window::ViewMode::Space {
+            // the values at the right and top edges of the view
+            let (position, velocity) = match window_info.view_mode {
+                window::ViewMode::PhaseX => ("x", "vx"),
+                _ => ("y", "vy"),
+            };
+            let (_, (right, top)) = window_info.visible_extent();
+            let axes = [
+                (
+                    format!("{} {:.1}", position, right),
+                    720.0,
+                    290.0,
+                ),
+                (
+                    format!("{} {:.1}", velocity, top),
+                    420.0,
+                    560.0,
+                ),
+            ];
+            for (text, label_x, label_y) in axes.iter() {
+                gl_draw::render_text(
+                    &gl,
+                    text,
+                    &ft_face,
+                    &ft_program,
+                    &mut ft_vao,
+                    &mut ft_vbo,
+                    &mut window_info,
+                    *label_x,
+                    *label_y,
+                    0.25,
+                );
+            }
+        }
+
         window.gl_swap_window();
     }
 }
+
+/* binds one slider per name, stacked under the expression line and squeezed together
+when there are too many to fit at the usual spacing; spare sliders are unbound rather
+than dropped so their GL objects get reused */
+fn layout_sliders(gl: &gl::Gl, sliders: &mut Vec<input::Slider>, names: Vec<String>) {
+    let (top, bottom) = (-0.33, -0.97);
+    let spacing = SLIDER_SPACING.min((top - bottom) / names.len().max(1) as f32);
+    while sliders.len() < names.len() {
+        sliders.push(input::Slider::new(gl, -0.95 + 0.55, 0.0, 0.45, 0.0, String::new()));
+    }
+    let mut names = names.into_iter();
+    for (i, slider) in sliders.iter_mut().enumerate() {
+        slider.y = top - spacing * i as f32;
+        slider.height = 0.06 * spacing / SLIDER_SPACING;
+        slider.parameter = names.next().unwrap_or_default();
+    }
+}
+
+/* sets the slider's parameter to the value under the cursor */
+fn set_from_slider(sim: &mut Simulator, slider: &input::Slider, c_x: f32) {
+    if let Some(p) = sim.parameter(slider.parameter()) {
+        let value = slider.value_at(c_x, p.min, p.max);
+        if let Err(e) = sim.set_parameter(&p.name, value) {
+            println!("{}", e);
+        }
+    }
+}
+
+/* a window pixel in the device coordinates of the button panel */
+fn panel_point(x: i32, y: i32, panel_size: i32) -> (f32, f32) {
+    (
+        x as f32 * 2.0 / panel_size as f32 - 1.0,
+        -(y as f32 * 2.0 / panel_size as f32 - 1.0),
+    )
+}
+
+/* the world point under a window pixel */
+fn world_at(window_info: &window::WindowData, x: i32, y: i32) -> PhysVector {
+    let (c_x, c_y) = window_info.pixel_to_screen(x, y);
+    let (x, y) = window_info.from_screen(c_x, c_y);
+    PhysVector { x, y }
+}
@@ -0,0 +1,249 @@
+use crate::gl_render::{self, Program};
+use crate::simulator::diagnostics::Diagnostics;
+use crate::simulator::Simulator;
+use gl;
+use std::collections::VecDeque;
+use std::ffi::CString;
+
+/* what the plot panel graphs against time */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PlotQuantity {
+    TotalEnergy,
+    KineticEnergy,
+    // the remaining ones follow the tracked particle
+    ParticleX,
+    ParticleY,
+    ParticleSpeed,
+}
+
+pub const PLOT_QUANTITIES: [PlotQuantity; 5] = [
+    PlotQuantity::TotalEnergy,
+    PlotQuantity::KineticEnergy,
+    PlotQuantity::ParticleX,
+    PlotQuantity::ParticleY,
+    PlotQuantity::ParticleSpeed,
+];
+
+impl PlotQuantity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlotQuantity::TotalEnergy => "Total Energy",
+            PlotQuantity::KineticEnergy => "Kinetic Energy",
+            PlotQuantity::ParticleX => "Particle x",
+            PlotQuantity::ParticleY => "Particle y",
+            PlotQuantity::ParticleSpeed => "Particle Speed",
+        }
+    }
+
+    /* None when there's nothing to plot, a non-conservative field or a missing particle */
+    pub fn value(&self, sim: &Simulator, diagnostics: &Diagnostics, particle: Option<u32>) -> Option<f32> {
+        let particles = sim.particle_list.borrow();
+        let tracked = particle.and_then(|id| particles.iter().find(|p| p.get_id() == id));
+        match self {
+            PlotQuantity::TotalEnergy => diagnostics.total_energy(),
+            PlotQuantity::KineticEnergy => Some(diagnostics.kinetic_energy),
+            PlotQuantity::ParticleX => tracked.map(|p| p.get_pos().x),
+            PlotQuantity::ParticleY => tracked.map(|p| p.get_pos().y),
+            PlotQuantity::ParticleSpeed => tracked.map(|p| {
+                let vel = p.get_vel();
+                vel.x.hypot(vel.y)
+            }),
+        }
+    }
+}
+
+/* a graph of one quantity over the last `window` seconds of simulation time, drawn in
+the rectangle with top left corner (x, y) */
+pub struct Plot {
+    gl: gl::Gl,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub program: Program,
+    pub vao_id: gl::types::GLuint,
+    pub vbo_id: gl::types::GLuint,
+    pub quantity: PlotQuantity,
+    // id of the particle the per-particle quantities follow
+    pub particle: Option<u32>,
+    pub window: f32,
+    // (time, value), oldest first
+    samples: VecDeque<(f32, f32)>,
+}
+
+impl Plot {
+    pub fn new(gl: &gl::Gl, x: f32, y: f32, width: f32, height: f32, window: f32) -> Plot {
+        Plot {
+            gl: gl.clone(),
+            x,
+            y,
+            width,
+            height,
+            program: Program::blank_program(gl),
+            vao_id: 0,
+            vbo_id: 0,
+            quantity: PlotQuantity::TotalEnergy,
+            particle: None,
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn set_quantity(&mut self, quantity: PlotQuantity) {
+        self.quantity = quantity;
+        self.samples.clear();
+    }
+
+    pub fn follows_particle(&self) -> bool {
+        !matches!(
+            self.quantity,
+            PlotQuantity::TotalEnergy | PlotQuantity::KineticEnergy
+        )
+    }
+
+    pub fn set_particle(&mut self, particle: Option<u32>) {
+        if particle != self.particle {
+            self.particle = particle;
+            self.samples.clear();
+        }
+    }
+
+    /* adds a point for the current time, forgetting the ones older than the window */
+    pub fn sample(&mut self, sim: &Simulator, diagnostics: &Diagnostics) {
+        let time = sim.time();
+        match self.samples.back() {
+            // clearing or loading a scene can send the clock backwards
+            Some(&(last, _)) if time < last => self.samples.clear(),
+            Some(&(last, _)) if time == last => return,
+            _ => {}
+        }
+        match self.quantity.value(sim, diagnostics, self.particle) {
+            Some(value) if value.is_finite() => self.samples.push_back((time, value)),
+            _ => self.samples.clear(),
+        }
+        while let Some(&(t, _)) = self.samples.front() {
+            if t >= time - self.window {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    /* (time, value) ranges covered by the axes, None before the first sample */
+    pub fn ranges(&self) -> Option<((f32, f32), (f32, f32))> {
+        let &(end, _) = self.samples.back()?;
+        let (mut low, mut high) = self
+            .samples
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(lo, hi), &(_, v)| (lo.min(v), hi.max(v)));
+        // leave room around a flat line instead of dividing by zero
+        let margin = ((high - low) * 0.05).max(high.abs().max(low.abs()) * 1e-4).max(1e-6);
+        low -= margin;
+        high += margin;
+        Some(((end - self.window, end), (low, high)))
+    }
+
+    /* label text and where its top left corner goes, in normalized device coordinates */
+    pub fn labels(&self) -> Vec<(String, f32, f32)> {
+        let mut labels = Vec::new();
+        if let Some(((start, end), (low, high))) = self.ranges() {
+            labels.push((format_value(high), self.x + 0.01, self.y - 0.01));
+            labels.push((format_value(low), self.x + 0.01, self.y - self.height + 0.06));
+            labels.push((
+                format!("t {:.1}", start.max(0.0)),
+                self.x + 0.01,
+                self.y - self.height - 0.01,
+            ));
+            labels.push((
+                format!("{:.1}", end),
+                self.x + self.width - 0.1,
+                self.y - self.height - 0.01,
+            ));
+        }
+        labels
+    }
+
+    pub fn draw(&mut self) {
+        if self.program.id() == 0 {
+            let vertex_shader = gl_render::Shader::from_vertex_source(
+                &self.gl,
+                &CString::new(include_str!("assets/shaders/line.vert")).unwrap(),
+            )
+            .unwrap();
+            let fragment_shader = gl_render::Shader::from_frag_source(
+                &self.gl,
+                &CString::new(include_str!("assets/shaders/line.frag")).unwrap(),
+            )
+            .unwrap();
+            self.program =
+                gl_render::Program::from_shaders(&self.gl, &[vertex_shader, fragment_shader]).unwrap();
+            unsafe {
+                self.gl.GenBuffers(1, &mut self.vbo_id);
+                self.gl.GenVertexArrays(1, &mut self.vao_id);
+                self.gl.BindVertexArray(self.vao_id);
+                self.gl.BindBuffer(gl::ARRAY_BUFFER, self.vbo_id);
+                self.gl.EnableVertexAttribArray(0);
+                self.gl.VertexAttribPointer(
+                    0,
+                    3,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    (3 * std::mem::size_of::<f32>()) as gl::types::GLint,
+                    std::ptr::null(),
+                );
+            }
+        }
+
+        let (left, right) = (self.x, self.x + self.width);
+        let (top, bottom) = (self.y, self.y - self.height);
+        // background, then the frame as a loop, then the data as a strip
+        let mut coordinates: Vec<f32> = vec![
+            left, top, 0.0,
+            right, top, 0.0,
+            right, bottom, 0.0,
+            left, bottom, 0.0,
+        ];
+        if let Some(((start, end), (low, high))) = self.ranges() {
+            for &(t, v) in &self.samples {
+                coordinates.push(left + (t - start) / (end - start) * self.width);
+                coordinates.push(bottom + (v - low) / (high - low) * self.height);
+                coordinates.push(0.0);
+            }
+        }
+
+        self.program.set_used();
+        let vertex_color_location = unsafe {
+            self.gl.GetUniformLocation(
+                self.program.id(),
+                (CString::new("inColor").unwrap()).as_ptr(),
+            )
+        };
+        unsafe {
+            self.gl.BindVertexArray(self.vao_id);
+            self.gl.BindBuffer(gl::ARRAY_BUFFER, self.vbo_id);
+            self.gl.BufferData(
+                gl::ARRAY_BUFFER,
+                (coordinates.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
+                coordinates.as_ptr() as *const gl::types::GLvoid,
+                gl::DYNAMIC_DRAW,
+            );
+            self.gl.Uniform4f(vertex_color_location, 1.0, 1.0, 1.0, 0.85);
+            self.gl.DrawArrays(gl::TRIANGLE_FAN, 0, 4);
+            self.gl.LineWidth(1.0);
+            self.gl.Uniform4f(vertex_color_location, 0.4, 0.4, 0.4, 1.0);
+            self.gl.DrawArrays(gl::LINE_LOOP, 0, 4);
+            self.gl.LineWidth(2.0);
+            self.gl.Uniform4f(vertex_color_location, 0.1, 0.3, 0.8, 1.0);
+            self.gl.DrawArrays(gl::LINE_STRIP, 4, (coordinates.len() / 3) as i32 - 4);
+        }
+    }
+}
+
+/* short enough to fit inside the panel */
+fn format_value(v: f32) -> String {
+    if v != 0.0 && (v.abs() >= 1e5 || v.abs() < 1e-2) {
+        format!("{:.3e}", v)
+    } else {
+        format!("{:.3}", v)
+    }
+}
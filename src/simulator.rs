@@ -1,33 +1,415 @@
+pub mod barnes_hut;
+pub mod boundary;
+pub mod collision;
+pub mod diagnostics;
+pub mod emitters;
+pub mod expression;
+pub mod integrator;
+pub mod interaction;
+pub mod joint;
+pub mod parameters;
+pub mod scene;
+pub mod sources;
+pub mod trajectory;
 pub mod util;
+use boundary::{Boundary, BoundaryMode};
+use emitters::{Emitter, Sink};
+use expression::FieldExpression;
+use integrator::Integrator;
+use interaction::{Interaction, Solver};
+use joint::MouseJoint;
+use parameters::{
+    ButterflyParams, DrivenHarmonicParams, GravityParams, GravityResistiveParams, HarmonicParams,
+    InverseSquareParams, LogisticParams, ParallelElectricParams, Parameter, ParameterSet,
+    RotatingDipoleParams, WindowsXPParams,
+};
+use serde::{Deserialize, Serialize};
+use sources::FieldSource;
 use std::cell::RefCell;
 use std::fmt;
 use std::rc::Rc;
 use util::PhysVector;
 
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub enum SimulateFunction {
-    Gravity,
-    GravityResistive,
-    Butterfly,
-    WindowsXP,
-    Logistic,
-    InverseSquare,
-    Harmonic,
-    ParallelElectric,
+    Gravity(GravityParams),
+    GravityResistive(GravityResistiveParams),
+    Butterfly(ButterflyParams),
+    WindowsXP(WindowsXPParams),
+    Logistic(LogisticParams),
+    InverseSquare(InverseSquareParams),
+    Harmonic(HarmonicParams),
+    ParallelElectric(ParallelElectricParams),
+    // harmonic trap in y pushed by a sinusoidal drive
+    DrivenHarmonic(DrivenHarmonicParams),
+    // a pair of opposite charges spinning about the origin
+    RotatingDipole(RotatingDipoleParams),
     NoForce,
+    // user-written acceleration field, see `FieldExpression`
+    Expression(FieldExpression),
+}
+
+pub const FUNCTION_NAMES: [&str; 11] = [
+    "Gravity",
+    "GravityResistive",
+    "Butterfly",
+    "WindowsXP",
+    "Logistic",
+    "InverseSquare",
+    "Harmonic",
+    "ParallelElectric",
+    "DrivenHarmonic",
+    "RotatingDipole",
+    "NoForce",
+];
+
+impl SimulateFunction {
+    /* the named function with its default parameters */
+    pub fn from_name(name: &str) -> Option<SimulateFunction> {
+        match name {
+            "Gravity" => Some(SimulateFunction::Gravity(Default::default())),
+            "GravityResistive" => Some(SimulateFunction::GravityResistive(Default::default())),
+            "Butterfly" => Some(SimulateFunction::Butterfly(Default::default())),
+            "WindowsXP" => Some(SimulateFunction::WindowsXP(Default::default())),
+            "Logistic" => Some(SimulateFunction::Logistic(Default::default())),
+            "InverseSquare" => Some(SimulateFunction::InverseSquare(Default::default())),
+            "Harmonic" => Some(SimulateFunction::Harmonic(Default::default())),
+            "ParallelElectric" => Some(SimulateFunction::ParallelElectric(Default::default())),
+            "DrivenHarmonic" => Some(SimulateFunction::DrivenHarmonic(Default::default())),
+            "RotatingDipole" => Some(SimulateFunction::RotatingDipole(Default::default())),
+            "NoForce" => Some(SimulateFunction::NoForce),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SimulateFunction::Gravity(_) => "Gravity",
+            SimulateFunction::GravityResistive(_) => "GravityResistive",
+            SimulateFunction::Butterfly(_) => "Butterfly",
+            SimulateFunction::WindowsXP(_) => "WindowsXP",
+            SimulateFunction::Logistic(_) => "Logistic",
+            SimulateFunction::InverseSquare(_) => "InverseSquare",
+            SimulateFunction::Harmonic(_) => "Harmonic",
+            SimulateFunction::ParallelElectric(_) => "ParallelElectric",
+            SimulateFunction::DrivenHarmonic(_) => "DrivenHarmonic",
+            SimulateFunction::RotatingDipole(_) => "RotatingDipole",
+            SimulateFunction::NoForce => "NoForce",
+            SimulateFunction::Expression(_) => "Expression",
+        }
+    }
+
+    fn parameter_set(&mut self) -> Option<&mut dyn ParameterSet> {
+        match self {
+            SimulateFunction::Gravity(params) => Some(params),
+            SimulateFunction::GravityResistive(params) => Some(params),
+            SimulateFunction::Butterfly(params) => Some(params),
+            SimulateFunction::WindowsXP(params) => Some(params),
+            SimulateFunction::Logistic(params) => Some(params),
+            SimulateFunction::InverseSquare(params) => Some(params),
+            SimulateFunction::Harmonic(params) => Some(params),
+            SimulateFunction::ParallelElectric(params) => Some(params),
+            SimulateFunction::DrivenHarmonic(params) => Some(params),
+            SimulateFunction::RotatingDipole(params) => Some(params),
+            SimulateFunction::NoForce | SimulateFunction::Expression(_) => None,
+        }
+    }
+
+    pub fn parameters(&self) -> Vec<Parameter> {
+        match self {
+            SimulateFunction::Gravity(params) => params.parameters(),
+            SimulateFunction::GravityResistive(params) => params.parameters(),
+            SimulateFunction::Butterfly(params) => params.parameters(),
+            SimulateFunction::WindowsXP(params) => params.parameters(),
+            SimulateFunction::Logistic(params) => params.parameters(),
+            SimulateFunction::InverseSquare(params) => params.parameters(),
+            SimulateFunction::Harmonic(params) => params.parameters(),
+            SimulateFunction::ParallelElectric(params) => params.parameters(),
+            SimulateFunction::DrivenHarmonic(params) => params.parameters(),
+            SimulateFunction::RotatingDipole(params) => params.parameters(),
+            SimulateFunction::NoForce => Vec::new(),
+            // expression constants can mean anything, so allow ten times the written value
+            SimulateFunction::Expression(expression) => expression
+                .parameters()
+                .into_iter()
+                .zip(expression.parameter_defaults())
+                .map(|((name, value), (_, default))| {
+                    let span = if default == 0.0 { 10.0 } else { 10.0 * default.abs() };
+                    Parameter::new(&name, value, -span, span)
+                })
+                .collect(),
+        }
+    }
+
+    /* potential energy of a particle, for fields that are the gradient of one;
+    None for velocity or time dependent fields and user expressions */
+    pub fn potential(&self, p: &Particle) -> Option<f32> {
+        let position = &p.pos;
+        match *self {
+            SimulateFunction::Gravity(GravityParams { g }) => Some(g * p.mass * position.y),
+            SimulateFunction::Logistic(LogisticParams { k, capacity }) => {
+                let y = position.y;
+                Some(-position.x - k * (y * y / 2.0 - y * y * y / (3.0 * capacity)))
+            }
+            SimulateFunction::InverseSquare(InverseSquareParams { strength }) => {
+                let r = (position.x * position.x + position.y * position.y).sqrt();
+                Some(-strength * p.mass / r)
+            }
+            SimulateFunction::Harmonic(HarmonicParams { k }) => {
+                Some(0.5 * k * position.y * position.y)
+            }
+            SimulateFunction::ParallelElectric(ParallelElectricParams {
+                strength,
+                separation,
+                length,
+            }) => {
+                let half = length / 2.0;
+                let top = sources::line_potential(
+                    &PhysVector { x: -half, y: separation / 2.0 },
+                    &PhysVector { x: half, y: separation / 2.0 },
+                    position,
+                );
+                let bottom = sources::line_potential(
+                    &PhysVector { x: -half, y: -separation / 2.0 },
+                    &PhysVector { x: half, y: -separation / 2.0 },
+                    position,
+                );
+                Some(strength * p.charge * (top - bottom))
+            }
+            SimulateFunction::NoForce => Some(0.0),
+            _ => None,
+        }
+    }
+
+    /* whether the field changes with time even where nothing moves */
+    pub fn depends_on_time(&self) -> bool {
+        match self {
+            SimulateFunction::DrivenHarmonic(_) | SimulateFunction::RotatingDipole(_) => true,
+            SimulateFunction::Expression(expression) => expression.uses_time(),
+            _ => false,
+        }
+    }
+
+    pub fn set_parameter(&mut self, name: &str, value: f32) -> Result<(), String> {
+        let parameter = self
+            .parameters()
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| format!("{} has no parameter \"{}\"", self.name(), name))?;
+        parameter.check(value)?;
+        if let SimulateFunction::Expression(expression) = self {
+            return expression.set_parameter(name, value);
+        }
+        if let Some(slot) = self.parameter_set().and_then(|set| set.value_mut(name)) {
+            *slot = value;
+        }
+        Ok(())
+    }
+
+    /* gravitational fields scale with mass, electric fields with charge */
+    pub fn force(&self, p: &Particle, time: f32) -> PhysVector {
+        let position = &p.pos;
+        match *self {
+            // forces
+            SimulateFunction::Gravity(GravityParams { g }) => PhysVector {
+                x: 0.0,
+                y: -g * p.mass,
+            },
+            SimulateFunction::GravityResistive(GravityResistiveParams { g, c }) => {
+                let a = PhysVector { x: 0.0, y: -g };
+                let v = &p.vel;
+                &(&a * p.mass) + &(v * c)
+            }
+            SimulateFunction::Butterfly(ButterflyParams { strength }) => {
+                let theta = position.x.atan2(position.y);
+                PhysVector {
+                    x: -(theta.cos() * position.x) * strength,
+                    y: -(theta.sin() * position.y) * strength,
+                }
+            }
+            SimulateFunction::WindowsXP(WindowsXPParams { pull }) => {
+                let theta = position.y.atan2(position.x);
+                PhysVector {
+                    x: (theta - pull).sin() * position.x.abs(),
+                    y: -((theta - pull).cos() * position.y.abs()),
+                }
+            }
+            SimulateFunction::Logistic(LogisticParams { k, capacity }) => PhysVector {
+                x: 1.0,
+                y: k * position.y * (1.0 - (position.y / capacity)),
+            },
+            SimulateFunction::InverseSquare(InverseSquareParams { strength }) => {
+                let theta = position.y.atan2(position.x);
+                let rad_squared = position.x * position.x + position.y * position.y;
+                let mag = strength * p.mass / rad_squared;
+                PhysVector {
+                    x: -theta.cos() * mag,
+                    y: -theta.sin() * mag
+                }
+            }
+            SimulateFunction::ParallelElectric(ParallelElectricParams {
+                strength,
+                separation,
+                length,
+            }) => {
+                // a positive plate above a negative one
+                let half = length / 2.0;
+                let top = sources::line_field(
+                    &PhysVector { x: -half, y: separation / 2.0 },
+                    &PhysVector { x: half, y: separation / 2.0 },
+                    position,
+                );
+                let bottom = sources::line_field(
+                    &PhysVector { x: -half, y: -separation / 2.0 },
+                    &PhysVector { x: half, y: -separation / 2.0 },
+                    position,
+                );
+                &(&top + &(&bottom * -1.0)) * (strength * p.charge)
+            }
+            SimulateFunction::Harmonic(HarmonicParams { k }) => PhysVector {
+                x: 0.0,
+                y: -k * position.y,
+            },
+            SimulateFunction::DrivenHarmonic(DrivenHarmonicParams {
+                k,
+                damping,
+                amplitude,
+                omega,
+            }) => PhysVector {
+                x: 0.0,
+                y: -k * position.y - damping * p.vel.y + amplitude * (omega * time).cos(),
+            },
+            SimulateFunction::RotatingDipole(RotatingDipoleParams {
+                strength,
+                separation,
+                omega,
+            }) => {
+                let angle = omega * time;
+                let half = PhysVector {
+                    x: angle.cos() * separation / 2.0,
+                    y: angle.sin() * separation / 2.0,
+                };
+                // softened so particles passing through a pole stay finite
+                let softening = 1.0;
+                let mut output = PhysVector { x: 0.0, y: 0.0 };
+                for (pole, sign) in [(&half * 1.0, 1.0), (&half * -1.0, -1.0)].iter() {
+                    let dx = position.x - pole.x;
+                    let dy = position.y - pole.y;
+                    let r2 = dx * dx + dy * dy + softening * softening;
+                    let mag = sign * strength / (r2 * r2.sqrt());
+                    output = &output + &PhysVector {
+                        x: dx * mag,
+                        y: dy * mag,
+                    };
+                }
+                &output * p.charge
+            }
+            SimulateFunction::Expression(ref expression) => {
+                &expression.acceleration(p, time) * p.mass
+            }
+            SimulateFunction::NoForce => PhysVector { x: 0.0, y: 0.0 },
+        }
+    }
+}
+
+/* one term of the external field, scaled by `weight` and skipped while disabled */
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForceSource {
+    pub function: SimulateFunction,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ForceSource {
+    pub fn new(function: SimulateFunction) -> ForceSource {
+        ForceSource {
+            function,
+            weight: 1.0,
+            enabled: true,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.function.name()
+    }
+
+    /* the weight followed by the function's constants, named "<function>.<constant>" */
+    pub fn parameters(&self) -> Vec<Parameter> {
+        let mut parameters = vec![Parameter::new("weight", self.weight, -5.0, 5.0)];
+        parameters.extend(self.function.parameters());
+        for p in parameters.iter_mut() {
+            p.name = format!("{}.{}", self.name(), p.name);
+        }
+        parameters
+    }
+
+    /* the weight and every constant within its range */
+    pub fn check(&self) -> Result<(), String> {
+        self.parameters().iter().try_for_each(|p| p.check(p.value))
+    }
 }
 
 pub struct Simulator {
     pub particle_list: Rc<RefCell<Vec<Particle>>>,
     steps: usize,
+    // simulated seconds, summed step by step so a timestep change doesn't rescale the
+    // time already passed; f64 keeps long runs from losing small steps to rounding
+    time: f64,
+    next_id: u32,
     timestep: f32,
-    function: SimulateFunction,
-    pub bounce_off_walls: bool,
+    // at most one source per function name
+    forces: Vec<ForceSource>,
+    // fixed charges and masses, always on
+    sources: Vec<FieldSource>,
+    // bumped whenever the external field changes, so views of it know to redraw
+    field_revision: u64,
+    // total energy when stepping started, remeasured after the system is changed
+    energy_reference: Option<f32>,
+    energy_reference_stale: bool,
+    integrator: Box<dyn Integrator>,
+    interaction: Interaction,
+    solver: Solver,
+    boundary: Boundary,
+    // the particle being dragged with the mouse, if any
+    joint: Option<MouseJoint>,
+    emitters: Vec<Emitter>,
+    sinks: Vec<Sink>,
+    // emitters draw from a generator seeded with this and the step count, so a run
+    // picked up from a saved scene emits the same particles as the original
+    seed: u64,
+    pub collide_particles: bool,
+    pub restitution: f32,
 }
 
 /* a simulated particle */
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Particle {
+    // assigned by the simulator, stable for the particle's lifetime
+    #[serde(default)]
+    id: u32,
     pos: PhysVector,
     vel: PhysVector,
+    mass: f32,
+    charge: f32,
+    radius: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+    // held in place, still pulling on the others through interactions
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    frozen: bool,
+    // simulation time it is removed at, for particles from emitters with a lifetime
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<f32>,
 }
 
 impl Simulator {
@@ -35,186 +417,546 @@ impl Simulator {
         Simulator {
             particle_list: Rc::new(RefCell::new(Vec::new())),
             steps: 0,
-            timestep: timestep,
-            function: SimulateFunction::Gravity,
-            bounce_off_walls: false,
+            time: 0.0,
+            next_id: 0,
+            timestep,
+            forces: vec![ForceSource::new(SimulateFunction::Gravity(
+                GravityParams::default(),
+            ))],
+            sources: Vec::new(),
+            field_revision: 0,
+            energy_reference: None,
+            energy_reference_stale: true,
+            integrator: Box::new(integrator::SemiImplicitEuler),
+            interaction: Interaction::None,
+            solver: Solver::Direct,
+            boundary: Boundary::centered(30.0, 30.0, BoundaryMode::Open),
+            joint: None,
+            emitters: Vec::new(),
+            sinks: Vec::new(),
+            seed: 0,
+            collide_particles: false,
+            restitution: 1.0,
         }
     }
 
     pub fn clear(&mut self) {
         self.particle_list = Rc::new(RefCell::new(Vec::new()));
+        self.joint = None;
+        self.energy_reference_stale = true;
     }
 
+    /* replaces every force source with just this one */
     pub fn set_function(&mut self, func: SimulateFunction) {
-        self.function = func;
+        self.set_forces(vec![ForceSource::new(func)]);
     }
 
-    pub fn step(simulator: Rc<RefCell<Simulator>>, count: usize) {
-        let avg_velocity = false;
-        {
-            let sim = simulator.borrow();
-            let particle_list = &mut sim.particle_list.borrow_mut();
-            for p in particle_list.iter_mut() {
-                let prev_vel = p.vel.clone();
-                for _i in 0..count {
-                    p.vel = &(&sim.acceleration_for(&p) * sim.timestep.into()) + &p.vel;
-                    if avg_velocity {
-                        p.pos = &(&(&p.vel + &prev_vel) * (0.5 * sim.timestep)) + &p.pos;
-                    } else {
-                        p.pos = &(&p.vel * sim.timestep) + &p.pos;
-                    }
-                    if sim.bounce_off_walls {
-                        if p.pos.x > 30.0 || p.pos.x < -30.0 {
-                            if p.pos.x > 30.0 && p.vel.x > 0.0 {
-                                p.vel.x = p.vel.x * -1.0;
-                            } else if p.pos.x < -30.0 && p.vel.x < 0.0 {
-                                p.vel.x = p.vel.x * -1.0;
-                            }
-                        }
-                        if p.pos.y > 30.0 || p.pos.y < -30.0 {
-                            if p.pos.y > 30.0 && p.vel.y > 0.0 {
-                                p.vel.y = p.vel.y * -1.0;
-                            } else if p.pos.y < -30.0 && p.vel.y < 0.0 {
-                                p.vel.y = p.vel.y * -1.0;
-                            }
-                        }
-                    }
-                }
+    pub fn forces(&self) -> &[ForceSource] {
+        &self.forces
+    }
+
+    pub fn set_forces(&mut self, forces: Vec<ForceSource>) {
+        self.forces = Vec::new();
+        for source in forces {
+            self.add_force(source);
+        }
+    }
+
+    /* adds an enabled source, replacing any existing one with the same function name */
+    pub fn add_force(&mut self, source: ForceSource) {
+        match self.forces.iter_mut().find(|s| s.name() == source.name()) {
+            Some(existing) => *existing = source,
+            None => self.forces.push(source),
+        }
+        self.field_changed();
+    }
+
+    pub fn force_enabled(&self, name: &str) -> bool {
+        self.forces.iter().any(|s| s.name() == name && s.enabled)
+    }
+
+    /* switches the named source on or off, adding it with default parameters the
+    first time a built-in is enabled */
+    pub fn set_force_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        match self.forces.iter_mut().find(|s| s.name() == name) {
+            Some(source) => source.enabled = enabled,
+            None if !enabled => return Ok(()),
+            None => {
+                let function = SimulateFunction::from_name(name)
+                    .ok_or_else(|| format!("no force source \"{}\"", name))?;
+                self.forces.push(ForceSource::new(function));
             }
         }
-        simulator.borrow_mut().steps += count;
+        self.field_changed();
+        Ok(())
     }
 
-    pub fn add_particle(&mut self, x_coord: f32, y_coord: f32, x_vel: f32, y_vel: f32) {
-        self.particle_list.borrow_mut().push(Particle {
-            pos: PhysVector {
-                x: x_coord,
-                y: y_coord,
-            },
-            vel: PhysVector { x: x_vel, y: y_vel },
-        });
+    pub fn toggle_force(&mut self, name: &str) -> Result<(), String> {
+        let enabled = self.force_enabled(name);
+        self.set_force_enabled(name, !enabled)
     }
 
-    pub fn acceleration_for(&self, p: &Particle) -> PhysVector {
-        let position = &p.pos;
-        match self.function {
-            // forces
-            SimulateFunction::Gravity => PhysVector { x: 0.0, y: -20.0 },
-            SimulateFunction::GravityResistive => {
-                let c: f32 = -0.7;
-                let a = PhysVector { x: 0.0, y: -9.8 };
-                let v = &p.vel;
-                &a + &(v * c)
+    pub fn field_revision(&self) -> u64 {
+        self.field_revision
+    }
+
+    fn field_changed(&mut self) {
+        self.field_revision += 1;
+        self.energy_reference_stale = true;
+    }
+
+    pub fn sources(&self) -> &[FieldSource] {
+        &self.sources
+    }
+
+    pub fn set_sources(&mut self, sources: Vec<FieldSource>) {
+        self.sources = sources;
+        self.field_changed();
+    }
+
+    /* returns the new source's index */
+    pub fn add_source(&mut self, source: FieldSource) -> usize {
+        self.sources.push(source);
+        self.field_changed();
+        self.sources.len() - 1
+    }
+
+    pub fn remove_source(&mut self, index: usize) -> Option<FieldSource> {
+        if index >= self.sources.len() {
+            return None;
+        }
+        self.field_changed();
+        Some(self.sources.remove(index))
+    }
+
+    /* moves a source so its center lands on (x, y) */
+    pub fn move_source(&mut self, index: usize, x: f32, y: f32) {
+        if let Some(source) = self.sources.get_mut(index) {
+            let center = source.center();
+            source.translate(&PhysVector {
+                x: x - center.x,
+                y: y - center.y,
+            });
+            self.field_changed();
+        }
+    }
+
+    /* the index of the closest source within `radius` of (x, y) */
+    pub fn source_near(&self, x: f32, y: f32, radius: f32) -> Option<usize> {
+        self.sources
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (i, s.distance_to(x, y)))
+            .filter(|(_, d)| *d <= radius)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(i, _)| i)
+    }
+
+    /* whether any enabled source varies with time, so views of the field go stale */
+    pub fn field_depends_on_time(&self) -> bool {
+        self.forces
+            .iter()
+            .any(|s| s.enabled && s.function.depends_on_time())
+    }
+
+    /* everything tunable at runtime: the timestep, collision restitution and the
+    weight and constants of each force source */
+    pub fn parameters(&self) -> Vec<Parameter> {
+        let mut parameters = vec![
+            Parameter::new("timestep", self.timestep, 0.0005, 0.05),
+            Parameter::new("restitution", self.restitution, 0.0, 1.0),
+        ];
+        for source in &self.forces {
+            parameters.extend(source.parameters());
+        }
+        parameters
+    }
+
+    pub fn parameter(&self, name: &str) -> Option<Parameter> {
+        self.parameters().into_iter().find(|p| p.name == name)
+    }
+
+    pub fn set_parameter(&mut self, name: &str, value: f32) -> Result<(), String> {
+        match name {
+            "timestep" => {
+                self.parameter(name).unwrap().check(value)?;
+                self.timestep = value;
             }
-            SimulateFunction::Butterfly => {
-                let theta = position.x.atan2(position.y);
-                PhysVector {
-                    x: theta.cos() * position.x * -1.0,
-                    y: theta.sin() * position.y * -1.0,
-                }
+            "restitution" => {
+                self.parameter(name).unwrap().check(value)?;
+                self.restitution = value;
+                self.energy_reference_stale = true;
             }
-            SimulateFunction::WindowsXP => {
-                let pull = 2.0;
-                let theta = position.y.atan2(position.x);
-                PhysVector {
-                    x: (theta - pull).sin() * position.x.abs(),
-                    y: (theta - pull).cos() * position.y.abs() * -1.0,
+            _ => {
+                let (source_name, constant) = name
+                    .split_once('.')
+                    .ok_or_else(|| format!("no parameter \"{}\"", name))?;
+                let source = self
+                    .forces
+                    .iter_mut()
+                    .find(|s| s.name() == source_name)
+                    .ok_or_else(|| format!("no force source \"{}\"", source_name))?;
+                if constant == "weight" {
+                    source.parameters()[0].check(value)?;
+                    source.weight = value;
+                } else {
+                    source.function.set_parameter(constant, value)?;
                 }
+                self.field_changed();
             }
-            SimulateFunction::Logistic => {
-                let k = 0.8;
-                #[allow(non_snake_case)]
-                let L = 15.0;
-                PhysVector {
-                    x: 1.0,
-                    y: k * position.y * (1.0 - (position.y / L)),
-                }
+        }
+        Ok(())
+    }
+
+    pub fn set_timestep(&mut self, timestep: f32) {
+        self.timestep = timestep;
+    }
+
+    pub fn timestep(&self) -> f32 {
+        self.timestep
+    }
+
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    /* switches to the next mode, keeping the rectangle, which may have come from a scene */
+    pub fn cycle_boundary_mode(&mut self) {
+        self.boundary.mode = self.boundary.mode.next();
+    }
+
+    pub fn set_integrator(&mut self, integrator: Box<dyn Integrator>) {
+        self.integrator = integrator;
+    }
+
+    pub fn integrator(&self) -> &dyn Integrator {
+        self.integrator.as_ref()
+    }
+
+    pub fn set_interaction(&mut self, interaction: Interaction) {
+        self.interaction = interaction;
+        self.energy_reference_stale = true;
+    }
+
+    pub fn interaction(&self) -> Interaction {
+        self.interaction
+    }
+
+    pub fn set_solver(&mut self, solver: Solver) {
+        self.solver = solver;
+    }
+
+    pub fn solver(&self) -> Solver {
+        self.solver
+    }
+
+    /* external field at `time` plus pairwise interactions, computed for every
+    particle before any of them move */
+    pub fn accelerations(&self, particles: &[Particle], time: f32) -> Vec<PhysVector> {
+        let pair_forces = match self.solver {
+            Solver::Direct => interaction::pairwise_forces(&self.interaction, particles),
+            Solver::BarnesHut { theta } => {
+                barnes_hut::barnes_hut_forces(&self.interaction, particles, theta)
             }
-            SimulateFunction::InverseSquare => {
-                let theta = position.y.atan2(position.x);
-                let rad_squared = position.x * position.x + position.y * position.y;
-                let mag = 300.0/rad_squared;
-                PhysVector {
-                    x: -theta.cos() * mag,
-                    y: -theta.sin() * mag
+        };
+        particles
+            .iter()
+            .zip(pair_forces.iter())
+            .map(|(p, f)| {
+                let a = &(&self.force_for(p, time) + f) * (1.0 / p.mass);
+                match &self.joint {
+                    Some(joint) if joint.particle == p.id => &a + &joint.acceleration(p),
+                    _ => a,
                 }
-            }
-            SimulateFunction::ParallelElectric => {
-                let particle_count = 200;
-                let particle_range = 30.0;
-                let particle_factor = 5.0;
-                let positive_force = |part_x: f32, part_y: f32| -> PhysVector {
-                    let dist: f32 =
-                        ((part_x - position.x).powf(2.0) + (part_y - position.y).powf(2.0)).sqrt();
-                    let mag: f32 = particle_factor / dist;
-                    let theta = (position.y - part_y).atan2(position.x - part_x);
-                    PhysVector {
-                        x: theta.cos() * mag,
-                        y: theta.sin() * mag,
-                    }
-                };
-                let negative_force = |part_x: f32, part_y: f32| -> PhysVector {
-                    let dist: f32 =
-                        ((part_x - position.x).powf(2.0) + (part_y - position.y).powf(2.0)).sqrt();
-                    let mag: f32 = particle_factor / dist;
-                    let theta = (position.y - part_y).atan2(position.x - part_x);
-                    PhysVector {
-                        x: -theta.cos() * mag,
-                        y: -theta.sin() * mag,
+            })
+            .collect()
+    }
+
+    /* attaches a particle to a point through a damped spring, replacing any earlier
+    joint; false if there is no particle with that id */
+    pub fn grab(&mut self, id: u32, x: f32, y: f32) -> bool {
+        if !self.particle_list.borrow().iter().any(|p| p.id == id) {
+            return false;
+        }
+        self.joint = Some(MouseJoint::new(id, PhysVector { x, y }));
+        true
+    }
+
+    pub fn move_grab(&mut self, x: f32, y: f32) {
+        if let Some(joint) = self.joint.as_mut() {
+            joint.target = PhysVector { x, y };
+        }
+    }
+
+    /* lets go of the grabbed particle, which keeps its velocity */
+    pub fn release(&mut self) {
+        if self.joint.take().is_some() {
+            // the spring did work on the system
+            self.energy_reference_stale = true;
+        }
+    }
+
+    pub fn joint(&self) -> Option<&MouseJoint> {
+        self.joint.as_ref()
+    }
+
+    pub fn step(simulator: Rc<RefCell<Simulator>>, count: usize) {
+        if simulator.borrow().energy_reference_stale {
+            let energy = simulator.borrow().diagnostics().total_energy();
+            let mut sim = simulator.borrow_mut();
+            sim.energy_reference = energy;
+            sim.energy_reference_stale = false;
+        }
+        for _ in 0..count {
+            let mut absorbed = Vec::new();
+            let mut absorbed_energy = Some(0.0);
+            {
+                let sim = simulator.borrow();
+                let particle_list = &mut sim.particle_list.borrow_mut();
+                let accel = |particles: &[Particle], time: f32| sim.accelerations(particles, time);
+                let time = sim.time();
+                let frozen: Vec<(usize, PhysVector)> = particle_list
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| p.frozen)
+                    .map(|(j, p)| (j, p.pos.clone()))
+                    .collect();
+                sim.integrator.step(particle_list, time, sim.timestep, &accel);
+                if sim.collide_particles {
+                    collision::resolve_collisions(particle_list, sim.restitution);
+                }
+                // put frozen particles back before the boundary can remove any
+                for (j, pos) in frozen {
+                    particle_list[j].pos = pos;
+                    particle_list[j].vel = PhysVector { x: 0.0, y: 0.0 };
+                }
+                if sim.boundary.mode == BoundaryMode::Absorb {
+                    absorbed = particle_list
+                        .iter()
+                        .filter(|p| !sim.boundary.contains(p.pos.x, p.pos.y))
+                        .map(|p| p.id)
+                        .collect();
+                    if !absorbed.is_empty() {
+                        absorbed_energy = sim.energy_of(particle_list, &absorbed);
                     }
-                };
-                let mut output = PhysVector { x: 0.0, y: 0.0 };
-                for ind in 0..particle_count {
-                    output = &output
-                        + &(&positive_force(
-                            (ind as f32) * particle_range / (particle_count as f32)
-                                - (particle_range / 2.0),
-                            20.0,
-                        ) + &negative_force(
-                            (ind as f32) * particle_range / (particle_count as f32)
-                                - (particle_range / 2.0),
-                            -20.0,
-                        ));
                 }
-                return output;
+                sim.boundary.apply(particle_list);
             }
-            SimulateFunction::Harmonic => {
-                let k = 10.0;
-                PhysVector {
-                    x: 0.0,
-                    y: -k * position.y,
+            let mut sim = simulator.borrow_mut();
+            sim.exchange_energy(absorbed_energy.map(|e| -e));
+            if !absorbed.is_empty() {
+                // the walls took these already, this lets go of a grabbed one
+                sim.take_particles(&absorbed);
+            }
+            sim.steps += 1;
+            sim.time += f64::from(sim.timestep);
+            sim.update_flow();
+        }
+    }
+
+    pub fn add_particle(
+        &mut self,
+        x_coord: f32,
+        y_coord: f32,
+        x_vel: f32,
+        y_vel: f32,
+        mass: f32,
+        charge: f32,
+    ) -> Result<(), String> {
+        self.push_particle(
+            Particle::new(x_coord, y_coord, x_vel, y_vel)
+                .with_mass(mass)
+                .with_charge(charge),
+        )
+    }
+
+    /* a zero or negative mass would give infinite accelerations */
+    pub fn push_particle(&mut self, p: Particle) -> Result<(), String> {
+        check_mass("particle", p.mass)?;
+        self.insert_particle(p);
+        self.energy_reference_stale = true;
+        Ok(())
+    }
+
+    /* gives the particle the next id and returns it, leaving the energy reference alone */
+    fn insert_particle(&mut self, mut p: Particle) -> u32 {
+        let id = self.next_id;
+        p.id = id;
+        self.next_id += 1;
+        self.particle_list.borrow_mut().push(p);
+        id
+    }
+
+    /* the id of the closest particle within `radius` of (x, y) */
+    pub fn particle_near(&self, x: f32, y: f32, radius: f32) -> Option<u32> {
+        self.particle_list
+            .borrow()
+            .iter()
+            .map(|p| (p.id, (p.pos.x - x).hypot(p.pos.y - y)))
+            .filter(|(_, d)| *d <= radius)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(id, _)| id)
+    }
+
+    pub fn particle(&self, id: u32) -> Option<Particle> {
+        self.particle_list
+            .borrow()
+            .iter()
+            .find(|p| p.id == id)
+            .cloned()
+    }
+
+    pub fn remove_particles(&mut self, ids: &[u32]) {
+        self.take_particles(ids);
+        self.energy_reference_stale = true;
+    }
+
+    fn take_particles(&mut self, ids: &[u32]) {
+        self.particle_list
+            .borrow_mut()
+            .retain(|p| !ids.contains(&p.id));
+        if self.joint.as_ref().is_some_and(|j| ids.contains(&j.particle)) {
+            self.joint = None;
+        }
+    }
+
+    /* frozen particles stay where they are with no velocity */
+    pub fn set_frozen(&mut self, ids: &[u32], frozen: bool) {
+        for p in self.particle_list.borrow_mut().iter_mut() {
+            if ids.contains(&p.id) {
+                p.frozen = frozen;
+                if frozen {
+                    p.vel = PhysVector { x: 0.0, y: 0.0 };
                 }
             }
-            _ => PhysVector { x: 0.0, y: 0.0 },
         }
+        self.energy_reference_stale = true;
+    }
+
+    /* acceleration from the external field alone */
+    pub fn acceleration_for(&self, p: &Particle, time: f32) -> PhysVector {
+        &self.force_for(p, time) * (1.0 / p.mass)
+    }
+
+    /* the weighted sum of every enabled force source at `time`, plus the field
+    sources */
+    pub fn force_for(&self, p: &Particle, time: f32) -> PhysVector {
+        let mut total = PhysVector { x: 0.0, y: 0.0 };
+        for source in self.forces.iter().filter(|s| s.enabled) {
+            total = &total + &(&source.function.force(p, time) * source.weight);
+        }
+        for source in &self.sources {
+            total = &total + &source.force_on(p);
+        }
+        total
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
     }
 
     pub fn time(&self) -> f32 {
-        self.steps as f32 * self.timestep
+        self.time as f32
+    }
+}
+
+pub fn check_mass(what: &str, mass: f32) -> Result<(), String> {
+    if mass.is_finite() && mass > 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{} mass must be positive, got {}", what, mass))
     }
 }
 
 impl Particle {
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
     pub fn get_pos(&self) -> PhysVector {
         self.pos.clone()
     }
 
+    pub fn get_vel(&self) -> PhysVector {
+        self.vel.clone()
+    }
+
+    pub fn get_mass(&self) -> f32 {
+        self.mass
+    }
+
+    pub fn get_charge(&self) -> f32 {
+        self.charge
+    }
+
+    pub fn get_radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn get_tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /* unit mass and charge, roughly the size it is drawn at, untagged */
     pub fn new(x: f32, y: f32, vx: f32, vy: f32) -> Particle {
         Particle {
-            pos: PhysVector { x: x, y: y },
+            id: 0,
+            pos: PhysVector { x, y },
             vel: PhysVector { x: vx, y: vy },
+            mass: 1.0,
+            charge: 1.0,
+            radius: 0.4,
+            tag: None,
+            frozen: false,
+            expires: None,
         }
     }
+
+    pub fn with_mass(mut self, mass: f32) -> Particle {
+        self.mass = mass;
+        self
+    }
+
+    pub fn with_charge(mut self, charge: f32) -> Particle {
+        self.charge = charge;
+        self
+    }
+
+    pub fn with_radius(mut self, radius: f32) -> Particle {
+        self.radius = radius;
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Particle {
+        self.tag = Some(tag.to_string());
+        self
+    }
 }
 
 impl fmt::Display for Simulator {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut output: String = String::from(format!("Time: {:.*}\n", 4, self.time()));
+        let mut output: String = format!("Time: {:.*}\n", 4, self.time());
         for v in self.particle_list.borrow().iter() {
             output.push_str(
                 format!(
-                    "[{:.*}, {:.*}], v=[{:.*}, {:.*}]\n",
-                    5, v.pos.x, 5, v.pos.y, 5, v.vel.x, 5, v.vel.y
+                    "[{:.*}, {:.*}], v=[{:.*}, {:.*}], m={}, q={}\n",
+                    5, v.pos.x, 5, v.pos.y, 5, v.vel.x, 5, v.vel.y, v.mass, v.charge
                 )
                 .as_str(),
             );
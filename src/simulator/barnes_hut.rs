@@ -0,0 +1,254 @@
+use super::interaction::Interaction;
+use super::util::PhysVector;
+use super::Particle;
+
+// coincident particles stop subdividing here and share a leaf
+const MAX_DEPTH: usize = 32;
+
+/* a square cell of the quadtree */
+struct Node {
+    center: PhysVector,
+    half_size: f32,
+    // net source strength (mass or charge) of everything in the cell
+    strength: f32,
+    // |strength|-weighted position sum, divided out into the cell's center of mass/charge
+    weight: f32,
+    weighted_pos: PhysVector,
+    // index of the first of four children, ordered SW, SE, NW, NE
+    children: Option<usize>,
+    particles: Vec<usize>,
+}
+
+impl Node {
+    fn new(center: PhysVector, half_size: f32) -> Node {
+        Node {
+            center,
+            half_size,
+            strength: 0.0,
+            weight: 0.0,
+            weighted_pos: PhysVector { x: 0.0, y: 0.0 },
+            children: None,
+            particles: Vec::new(),
+        }
+    }
+
+    fn contains(&self, pos: &PhysVector) -> bool {
+        (pos.x - self.center.x).abs() <= self.half_size
+            && (pos.y - self.center.y).abs() <= self.half_size
+    }
+
+    fn quadrant(&self, pos: &PhysVector) -> usize {
+        let east = if pos.x >= self.center.x { 1 } else { 0 };
+        let north = if pos.y >= self.center.y { 2 } else { 0 };
+        east + north
+    }
+
+    fn source_center(&self) -> PhysVector {
+        if self.weight > 0.0 {
+            &self.weighted_pos * (1.0 / self.weight)
+        } else {
+            self.center.clone()
+        }
+    }
+}
+
+pub struct QuadTree<'a> {
+    nodes: Vec<Node>,
+    interaction: Interaction,
+    particles: &'a [Particle],
+    strengths: Vec<f32>,
+}
+
+impl<'a> QuadTree<'a> {
+    pub fn new(interaction: Interaction, particles: &'a [Particle]) -> QuadTree<'a> {
+        let mut min = PhysVector {
+            x: f32::MAX,
+            y: f32::MAX,
+        };
+        let mut max = PhysVector {
+            x: f32::MIN,
+            y: f32::MIN,
+        };
+        for p in particles {
+            min.x = min.x.min(p.pos.x);
+            min.y = min.y.min(p.pos.y);
+            max.x = max.x.max(p.pos.x);
+            max.y = max.y.max(p.pos.y);
+        }
+        let center = if particles.is_empty() {
+            PhysVector { x: 0.0, y: 0.0 }
+        } else {
+            &(&min + &max) * 0.5
+        };
+        let half_size = ((max.x - min.x).max(max.y - min.y) * 0.5).max(1.0) * 1.01;
+
+        let mut tree = QuadTree {
+            nodes: vec![Node::new(center, half_size)],
+            interaction,
+            particles,
+            strengths: particles
+                .iter()
+                .map(|p| interaction.source_strength(p))
+                .collect(),
+        };
+        for i in 0..particles.len() {
+            tree.insert(0, i, 0);
+        }
+        tree
+    }
+
+    fn insert(&mut self, node: usize, index: usize, depth: usize) {
+        let particles = self.particles;
+        let pos = &particles[index].pos;
+        let strength = self.strengths[index];
+        {
+            let n = &mut self.nodes[node];
+            n.strength += strength;
+            n.weight += strength.abs();
+            n.weighted_pos = &n.weighted_pos + &(pos * strength.abs());
+        }
+
+        match self.nodes[node].children {
+            Some(first) => {
+                let child = first + self.nodes[node].quadrant(pos);
+                self.insert(child, index, depth + 1);
+            }
+            None => {
+                if self.nodes[node].particles.is_empty() || depth >= MAX_DEPTH {
+                    self.nodes[node].particles.push(index);
+                    return;
+                }
+                // split the leaf and push its occupants down a level
+                let first = self.nodes.len();
+                let center = self.nodes[node].center.clone();
+                let quarter = self.nodes[node].half_size * 0.5;
+                for (dx, dy) in &[(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+                    self.nodes.push(Node::new(
+                        PhysVector {
+                            x: center.x + dx * quarter,
+                            y: center.y + dy * quarter,
+                        },
+                        quarter,
+                    ));
+                }
+                self.nodes[node].children = Some(first);
+                let occupants = std::mem::take(&mut self.nodes[node].particles);
+                for other in occupants.into_iter().chain(std::iter::once(index)) {
+                    let child = first + self.nodes[node].quadrant(&particles[other].pos);
+                    self.insert(child, other, depth + 1);
+                }
+            }
+        }
+    }
+
+    /* a cell is treated as a single source once its width / distance falls below theta */
+    pub fn force_on(&self, index: usize, theta: f32) -> PhysVector {
+        let mut force = PhysVector { x: 0.0, y: 0.0 };
+        let pos = &self.particles[index].pos;
+        let strength = self.strengths[index];
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let n = &self.nodes[node];
+            if n.weight == 0.0 {
+                continue;
+            }
+            match n.children {
+                None => {
+                    for &other in &n.particles {
+                        if other != index {
+                            let f = self.interaction.force_from(
+                                pos,
+                                strength,
+                                &self.particles[other].pos,
+                                self.strengths[other],
+                            );
+                            force = &force + &f;
+                        }
+                    }
+                }
+                Some(first) => {
+                    let source = n.source_center();
+                    let dx = source.x - pos.x;
+                    let dy = source.y - pos.y;
+                    let dist = (dx * dx + dy * dy).sqrt();
+                    if !n.contains(pos) && 2.0 * n.half_size < theta * dist {
+                        let f = self
+                            .interaction
+                            .force_from(pos, strength, &source, n.strength);
+                        force = &force + &f;
+                    } else {
+                        stack.extend(first..first + 4);
+                    }
+                }
+            }
+        }
+        force
+    }
+
+    /* the same walk as force_on, summing the potential energy with every other particle */
+    pub fn potential_on(&self, index: usize, theta: f32) -> f32 {
+        let mut potential = 0.0;
+        let pos = &self.particles[index].pos;
+        let strength = self.strengths[index];
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let n = &self.nodes[node];
+            if n.weight == 0.0 {
+                continue;
+            }
+            match n.children {
+                None => {
+                    for &other in &n.particles {
+                        if other != index {
+                            potential += self.interaction.potential_from(
+                                pos,
+                                strength,
+                                &self.particles[other].pos,
+                                self.strengths[other],
+                            );
+                        }
+                    }
+                }
+                Some(first) => {
+                    let source = n.source_center();
+                    let dx = source.x - pos.x;
+                    let dy = source.y - pos.y;
+                    let dist = (dx * dx + dy * dy).sqrt();
+                    if !n.contains(pos) && 2.0 * n.half_size < theta * dist {
+                        potential += self
+                            .interaction
+                            .potential_from(pos, strength, &source, n.strength);
+                    } else {
+                        stack.extend(first..first + 4);
+                    }
+                }
+            }
+        }
+        potential
+    }
+}
+
+pub fn barnes_hut_forces(
+    interaction: &Interaction,
+    particles: &[Particle],
+    theta: f32,
+) -> Vec<PhysVector> {
+    if *interaction == Interaction::None {
+        return vec![PhysVector { x: 0.0, y: 0.0 }; particles.len()];
+    }
+    let tree = QuadTree::new(*interaction, particles);
+    (0..particles.len())
+        .map(|i| tree.force_on(i, theta))
+        .collect()
+}
+
+/* approximates pairwise_potential, each pair is seen from both ends so the sum is halved */
+pub fn barnes_hut_potential(interaction: &Interaction, particles: &[Particle], theta: f32) -> f32 {
+    if *interaction == Interaction::None {
+        return 0.0;
+    }
+    let tree = QuadTree::new(*interaction, particles);
+    0.5 * (0..particles.len())
+        .map(|i| tree.potential_on(i, theta))
+        .sum::<f32>()
+}
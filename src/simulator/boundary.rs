@@ -0,0 +1,121 @@
+use super::Particle;
+use serde::{Deserialize, Serialize};
+
+/* what happens to a particle that leaves the world rectangle */
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BoundaryMode {
+    // bounce back in, keeping `restitution` of the normal velocity
+    Reflect { restitution: f32 },
+    // periodic: leave one side, enter on the opposite one
+    Wrap,
+    // delete the particle
+    Absorb,
+    // no walls at all
+    Open,
+}
+
+impl BoundaryMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoundaryMode::Reflect { .. } => "Reflect",
+            BoundaryMode::Wrap => "Wrap",
+            BoundaryMode::Absorb => "Absorb",
+            BoundaryMode::Open => "Open",
+        }
+    }
+
+    /* the mode after this one in the GUI's cycle */
+    pub fn next(&self) -> BoundaryMode {
+        match self {
+            BoundaryMode::Open => BoundaryMode::Reflect { restitution: 1.0 },
+            BoundaryMode::Reflect { .. } => BoundaryMode::Wrap,
+            BoundaryMode::Wrap => BoundaryMode::Absorb,
+            BoundaryMode::Absorb => BoundaryMode::Open,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Boundary {
+    pub x_min: f32,
+    pub x_max: f32,
+    pub y_min: f32,
+    pub y_max: f32,
+    pub mode: BoundaryMode,
+}
+
+impl Boundary {
+    /* the rectangle [-x_range, x_range] x [-y_range, y_range] */
+    pub fn centered(x_range: f32, y_range: f32, mode: BoundaryMode) -> Boundary {
+        Boundary {
+            x_min: -x_range,
+            x_max: x_range,
+            y_min: -y_range,
+            y_max: y_range,
+            mode,
+        }
+    }
+
+    /* an empty or inverted rectangle would wrap every position to NaN */
+    pub fn check(&self) -> Result<(), String> {
+        for (axis, min, max) in &[("x", self.x_min, self.x_max), ("y", self.y_min, self.y_max)] {
+            if !(min.is_finite() && max.is_finite() && min < max) {
+                return Err(format!(
+                    "boundary {} range must be finite and increasing, got {} to {}",
+                    axis, min, max
+                ));
+            }
+        }
+        if let BoundaryMode::Reflect { restitution } = self.mode {
+            if !(0.0..=1.0).contains(&restitution) {
+                return Err(format!(
+                    "boundary restitution must be between 0 and 1, got {}",
+                    restitution
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x_min && x <= self.x_max && y >= self.y_min && y <= self.y_max
+    }
+
+    pub fn apply(&self, particles: &mut Vec<Particle>) {
+        match self.mode {
+            BoundaryMode::Open => {}
+            BoundaryMode::Absorb => {
+                particles.retain(|p| self.contains(p.pos.x, p.pos.y));
+            }
+            BoundaryMode::Wrap => {
+                let width = self.x_max - self.x_min;
+                let height = self.y_max - self.y_min;
+                for p in particles.iter_mut() {
+                    p.pos.x = (p.pos.x - self.x_min).rem_euclid(width) + self.x_min;
+                    p.pos.y = (p.pos.y - self.y_min).rem_euclid(height) + self.y_min;
+                }
+            }
+            BoundaryMode::Reflect { restitution } => {
+                for p in particles.iter_mut() {
+                    reflect(&mut p.pos.x, &mut p.vel.x, self.x_min, self.x_max, restitution);
+                    reflect(&mut p.pos.y, &mut p.vel.y, self.y_min, self.y_max, restitution);
+                }
+            }
+        }
+    }
+}
+
+/* mirrors a coordinate that overshot a wall back inside */
+fn reflect(pos: &mut f32, vel: &mut f32, min: f32, max: f32, restitution: f32) {
+    if *pos > max {
+        *pos = (max - (*pos - max) * restitution).max(min);
+        if *vel > 0.0 {
+            *vel = -*vel * restitution;
+        }
+    } else if *pos < min {
+        *pos = (min + (min - *pos) * restitution).min(max);
+        if *vel < 0.0 {
+            *vel = -*vel * restitution;
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use super::Particle;
+use std::collections::HashMap;
+
+/* uniform grid broad phase: only particles in neighbouring cells are tested */
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32, particles: &[Particle]) -> SpatialHash {
+        let mut hash = SpatialHash {
+            cell_size,
+            cells: HashMap::new(),
+        };
+        for (i, p) in particles.iter().enumerate() {
+            let cell = hash.cell_for(p);
+            hash.cells.entry(cell).or_default().push(i);
+        }
+        hash
+    }
+
+    fn cell_for(&self, p: &Particle) -> (i32, i32) {
+        (
+            (p.pos.x / self.cell_size).floor() as i32,
+            (p.pos.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /* every pair (i, j), i < j, close enough to possibly touch */
+    pub fn candidate_pairs(&self, particles: &[Particle]) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for (&(cx, cy), members) in &self.cells {
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    if let Some(others) = self.cells.get(&(cx + dx, cy + dy)) {
+                        for &i in members {
+                            for &j in others {
+                                if i < j && touching(&particles[i], &particles[j]) {
+                                    pairs.push((i, j));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        pairs
+    }
+}
+
+fn touching(a: &Particle, b: &Particle) -> bool {
+    let dx = b.pos.x - a.pos.x;
+    let dy = b.pos.y - a.pos.y;
+    let reach = a.radius + b.radius;
+    dx * dx + dy * dy < reach * reach
+}
+
+/* exchanges impulses between overlapping, approaching particles and pushes them apart.
+a restitution of 1 is perfectly elastic, 0 perfectly inelastic */
+pub fn resolve_collisions(particles: &mut [Particle], restitution: f32) {
+    let max_radius = particles.iter().fold(0.0_f32, |r, p| r.max(p.radius));
+    if max_radius <= 0.0 {
+        return;
+    }
+    let hash = SpatialHash::new(2.0 * max_radius, particles);
+    let mut pairs = hash.candidate_pairs(particles);
+    // hash map iteration order is arbitrary, keep the result reproducible
+    pairs.sort_unstable();
+
+    for (i, j) in pairs {
+        let (left, right) = particles.split_at_mut(j);
+        let a = &mut left[i];
+        let b = &mut right[0];
+
+        let dx = b.pos.x - a.pos.x;
+        let dy = b.pos.y - a.pos.y;
+        let dist = (dx * dx + dy * dy).sqrt();
+        let reach = a.radius + b.radius;
+        if dist >= reach {
+            continue;
+        }
+        let (nx, ny) = if dist > 0.0 {
+            (dx / dist, dy / dist)
+        } else {
+            (1.0, 0.0)
+        };
+        // frozen particles act as infinitely heavy walls
+        let inv_a = if a.frozen { 0.0 } else { 1.0 / a.mass };
+        let inv_b = if b.frozen { 0.0 } else { 1.0 / b.mass };
+        if inv_a + inv_b == 0.0 {
+            continue;
+        }
+
+        // separate along the normal, the lighter particle moving further
+        let overlap = reach - dist;
+        let push = overlap / (inv_a + inv_b);
+        a.pos.x -= nx * push * inv_a;
+        a.pos.y -= ny * push * inv_a;
+        b.pos.x += nx * push * inv_b;
+        b.pos.y += ny * push * inv_b;
+
+        let closing = (b.vel.x - a.vel.x) * nx + (b.vel.y - a.vel.y) * ny;
+        if closing < 0.0 {
+            let impulse = -(1.0 + restitution) * closing / (inv_a + inv_b);
+            a.vel.x -= nx * impulse * inv_a;
+            a.vel.y -= ny * impulse * inv_a;
+            b.vel.x += nx * impulse * inv_b;
+            b.vel.y += ny * impulse * inv_b;
+        }
+    }
+}
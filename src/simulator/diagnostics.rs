@@ -0,0 +1,122 @@
+use super::barnes_hut;
+use super::interaction::{self, Interaction, Solver};
+use super::util::PhysVector;
+use super::{Particle, Simulator};
+use std::collections::HashMap;
+
+/* conserved quantities of the whole system at one instant */
+#[derive(Clone)]
+pub struct Diagnostics {
+    pub time: f32,
+    pub kinetic_energy: f32,
+    // None while any enabled force function isn't conservative
+    pub potential_energy: Option<f32>,
+    pub momentum: PhysVector,
+    // about the origin, positive counterclockwise
+    pub angular_momentum: f32,
+    // (E - E0) / |E0| since stepping started, less energy that flowed in or out
+    pub energy_drift: Option<f32>,
+}
+
+impl Diagnostics {
+    pub fn total_energy(&self) -> Option<f32> {
+        self.potential_energy.map(|u| u + self.kinetic_energy)
+    }
+}
+
+impl Simulator {
+    /* kinetic energy plus the potential of the force functions, field sources and
+    pairwise interactions */
+    pub fn diagnostics(&self) -> Diagnostics {
+        let particles = self.particle_list.borrow();
+        let mut kinetic_energy = 0.0;
+        let mut momentum = PhysVector { x: 0.0, y: 0.0 };
+        let mut angular_momentum = 0.0;
+        for p in particles.iter() {
+            kinetic_energy += 0.5 * p.mass * (p.vel.x * p.vel.x + p.vel.y * p.vel.y);
+            momentum = &momentum + &(&p.vel * p.mass);
+            angular_momentum += p.mass * (p.pos.x * p.vel.y - p.pos.y * p.vel.x);
+        }
+
+        let pairwise = match self.solver {
+            Solver::Direct => interaction::pairwise_potential(&self.interaction, &particles),
+            Solver::BarnesHut { theta } => {
+                barnes_hut::barnes_hut_potential(&self.interaction, &particles, theta)
+            }
+        };
+        let potential_energy = particles
+            .iter()
+            .try_fold(pairwise, |u, p| self.external_potential(p).map(|v| u + v));
+
+        let total = potential_energy.map(|u| u + kinetic_energy);
+        // before the next step the reference will be the current energy
+        let reference = if self.energy_reference_stale {
+            total
+        } else {
+            self.energy_reference
+        };
+        let energy_drift = match (total, reference) {
+            (Some(e), Some(e0)) if e0 != 0.0 => Some((e - e0) / e0.abs()),
+            _ => None,
+        };
+        Diagnostics {
+            time: self.time(),
+            kinetic_energy,
+            potential_energy,
+            momentum,
+            angular_momentum,
+            energy_drift,
+        }
+    }
+
+    /* potential of one particle in the force functions and field sources */
+    fn external_potential(&self, p: &Particle) -> Option<f32> {
+        let mut u = 0.0;
+        for source in self.forces.iter().filter(|s| s.enabled) {
+            u += source.weight * source.function.potential(p)?;
+        }
+        Some(u + self.sources.iter().map(|s| s.potential_on(p)).sum::<f32>())
+    }
+
+    /* energy the particles `ids` carry: their own kinetic and external potential
+    energy plus their pairwise potential with the rest, each pair counted once */
+    pub(super) fn energy_of(&self, particles: &[Particle], ids: &[u32]) -> Option<f32> {
+        let order: HashMap<u32, usize> = ids.iter().enumerate().map(|(n, &id)| (id, n)).collect();
+        let mut total = 0.0;
+        for p in particles {
+            let n = match order.get(&p.id) {
+                Some(&n) => n,
+                None => continue,
+            };
+            total += 0.5 * p.mass * (p.vel.x * p.vel.x + p.vel.y * p.vel.y);
+            total += self.external_potential(p)?;
+            if self.interaction == Interaction::None {
+                continue;
+            }
+            let strength = self.interaction.source_strength(p);
+            // a pair within `ids` is counted by whichever of the two comes first
+            for other in particles {
+                if other.id != p.id && order.get(&other.id).is_none_or(|&m| m > n) {
+                    total += self.interaction.potential_from(
+                        &p.pos,
+                        strength,
+                        &other.pos,
+                        self.interaction.source_strength(other),
+                    );
+                }
+            }
+        }
+        Some(total)
+    }
+
+    /* moves the drift reference along with energy that entered or left through
+    emitters, sinks and absorbing walls, so only integration error shows as drift */
+    pub(super) fn exchange_energy(&mut self, delta: Option<f32>) {
+        if !self.energy_reference_stale {
+            self.energy_reference = match (self.energy_reference, delta) {
+                (Some(e0), Some(delta)) => Some(e0 + delta),
+                _ => None,
+            };
+        }
+    }
+}
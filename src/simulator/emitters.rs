@@ -0,0 +1,359 @@
+use super::util::PhysVector;
+use super::{check_mass, Particle, Simulator};
+use rand::distributions::Normal;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+// particles per second an emitter may be set to release
+pub const MAX_RATE: f32 = 1e6;
+// most particles one emitter releases in a single step, however short the timestep
+pub const MAX_EMITTED_PER_STEP: f32 = 1000.0;
+
+/* where an emitter puts new particles */
+#[derive(Clone, Serialize, Deserialize)]
+pub enum EmitterShape {
+    Point { pos: PhysVector },
+    // particles start anywhere along the segment
+    Line { start: PhysVector, end: PhysVector },
+}
+
+/* how fast new particles leave an emitter */
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum SpeedDistribution {
+    Constant(f32),
+    Uniform { min: f32, max: f32 },
+    Normal { mean: f32, std_dev: f32 },
+}
+
+impl SpeedDistribution {
+    pub fn sample(&self, rng: &mut impl Rng) -> f32 {
+        match *self {
+            SpeedDistribution::Constant(speed) => speed,
+            SpeedDistribution::Uniform { min, max } if max > min => rng.gen_range(min, max),
+            SpeedDistribution::Uniform { min, .. } => min,
+            SpeedDistribution::Normal { mean, std_dev } => {
+                rng.sample(Normal::new(f64::from(mean), f64::from(std_dev.max(0.0)))) as f32
+            }
+        }
+    }
+
+    pub fn mean(&self) -> f32 {
+        match *self {
+            SpeedDistribution::Constant(speed) => speed,
+            SpeedDistribution::Uniform { min, max } => (min + max) / 2.0,
+            SpeedDistribution::Normal { mean, .. } => mean,
+        }
+    }
+}
+
+/* a source of new particles, releasing `rate` of them per second of simulation time */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Emitter {
+    pub shape: EmitterShape,
+    pub rate: f32,
+    pub speed: SpeedDistribution,
+    // mean launch direction in radians from the x axis
+    pub direction: f32,
+    // full width of the cone launch directions are spread over, in radians
+    pub spread: f32,
+    // seconds an emitted particle lives, None for as long as the simulation keeps it
+    #[serde(default)]
+    pub lifetime: Option<f32>,
+    pub mass: f32,
+    pub charge: f32,
+    // part of a particle owed from earlier steps
+    #[serde(default)]
+    backlog: f32,
+}
+
+impl Emitter {
+    pub fn point(pos: PhysVector, direction: f32, speed: f32) -> Emitter {
+        Emitter::new(EmitterShape::Point { pos }, direction, speed)
+    }
+
+    /* emits off the left side of the segment going from start to end */
+    pub fn line(start: PhysVector, end: PhysVector, speed: f32) -> Emitter {
+        let direction = (end.y - start.y).atan2(end.x - start.x) + std::f32::consts::FRAC_PI_2;
+        Emitter::new(EmitterShape::Line { start, end }, direction, speed)
+    }
+
+    fn new(shape: EmitterShape, direction: f32, speed: f32) -> Emitter {
+        Emitter {
+            shape,
+            rate: 20.0,
+            speed: SpeedDistribution::Normal {
+                mean: speed,
+                std_dev: speed * 0.1,
+            },
+            direction,
+            spread: 0.3,
+            lifetime: Some(20.0),
+            mass: 1.0,
+            charge: 1.0,
+            backlog: 0.0,
+        }
+    }
+
+    pub fn center(&self) -> PhysVector {
+        match &self.shape {
+            EmitterShape::Point { pos } => pos.clone(),
+            EmitterShape::Line { start, end } => &(start + end) * 0.5,
+        }
+    }
+
+    pub fn distance_to(&self, x: f32, y: f32) -> f32 {
+        match &self.shape {
+            EmitterShape::Point { pos } => (x - pos.x).hypot(y - pos.y),
+            EmitterShape::Line { start, end } => {
+                let (dx, dy) = (end.x - start.x, end.y - start.y);
+                let length_squared = dx * dx + dy * dy;
+                let t = if length_squared > 0.0 {
+                    (((x - start.x) * dx + (y - start.y) * dy) / length_squared).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                (x - start.x - t * dx).hypot(y - start.y - t * dy)
+            }
+        }
+    }
+
+    /* a rate outside (0, MAX_RATE], or a setting that isn't a number, is an error */
+    pub fn check(&self) -> Result<(), String> {
+        check_mass("emitter", self.mass)?;
+        if !(self.rate > 0.0 && self.rate <= MAX_RATE) {
+            return Err(format!(
+                "emitter rate must be positive and at most {}, got {}",
+                MAX_RATE, self.rate
+            ));
+        }
+        if !(self.spread.is_finite() && self.spread >= 0.0) {
+            return Err(format!(
+                "emitter spread must be zero or more, got {}",
+                self.spread
+            ));
+        }
+        if let Some(lifetime) = self.lifetime.filter(|l| !(l.is_finite() && *l > 0.0)) {
+            return Err(format!("emitter lifetime must be positive, got {}", lifetime));
+        }
+        let speeds = match self.speed {
+            SpeedDistribution::Constant(speed) => vec![speed],
+            SpeedDistribution::Uniform { min, max } => vec![min, max],
+            SpeedDistribution::Normal { mean, std_dev } => vec![mean, std_dev],
+        };
+        if !self.direction.is_finite() || speeds.iter().any(|v| !v.is_finite()) {
+            return Err(String::from("emitter direction and speed must be finite"));
+        }
+        Ok(())
+    }
+
+    /* the particles due over the next `dt` seconds, expiring `lifetime` after `time` */
+    fn emit(&mut self, rng: &mut impl Rng, time: f32, dt: f32) -> Vec<Particle> {
+        let mut particles = Vec::new();
+        // the fields are public, so they may have been changed since the emitter was added
+        if self.check().is_err() {
+            return particles;
+        }
+        self.backlog = (self.backlog + self.rate * dt).min(MAX_EMITTED_PER_STEP);
+        while self.backlog >= 1.0 {
+            self.backlog -= 1.0;
+            let pos = match &self.shape {
+                EmitterShape::Point { pos } => pos.clone(),
+                EmitterShape::Line { start, end } => {
+                    let t = rng.gen_range(0.0, 1.0);
+                    PhysVector {
+                        x: start.x + t * (end.x - start.x),
+                        y: start.y + t * (end.y - start.y),
+                    }
+                }
+            };
+            let angle = if self.spread > 0.0 {
+                self.direction + rng.gen_range(-self.spread / 2.0, self.spread / 2.0)
+            } else {
+                self.direction
+            };
+            let speed = self.speed.sample(rng);
+            let mut p = Particle::new(pos.x, pos.y, speed * angle.cos(), speed * angle.sin())
+                .with_mass(self.mass)
+                .with_charge(self.charge);
+            p.expires = self.lifetime.map(|lifetime| time + lifetime);
+            particles.push(p);
+        }
+        particles
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum SinkShape {
+    Circle { center: PhysVector, radius: f32 },
+    // axis aligned, between two opposite corners
+    Rect { min: PhysVector, max: PhysVector },
+}
+
+/* a region that removes every particle entering it */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Sink {
+    pub shape: SinkShape,
+    // how many particles it has taken, for measuring the flow through it
+    #[serde(default)]
+    pub absorbed: u64,
+}
+
+impl Sink {
+    pub fn circle(center: PhysVector, radius: f32) -> Sink {
+        Sink {
+            shape: SinkShape::Circle { center, radius },
+            absorbed: 0,
+        }
+    }
+
+    pub fn rect(a: PhysVector, b: PhysVector) -> Sink {
+        Sink {
+            shape: SinkShape::Rect {
+                min: PhysVector {
+                    x: a.x.min(b.x),
+                    y: a.y.min(b.y),
+                },
+                max: PhysVector {
+                    x: a.x.max(b.x),
+                    y: a.y.max(b.y),
+                },
+            },
+            absorbed: 0,
+        }
+    }
+
+    pub fn contains(&self, pos: &PhysVector) -> bool {
+        match &self.shape {
+            SinkShape::Circle { center, radius } => {
+                (pos.x - center.x).hypot(pos.y - center.y) <= *radius
+            }
+            SinkShape::Rect { min, max } => {
+                pos.x >= min.x && pos.x <= max.x && pos.y >= min.y && pos.y <= max.y
+            }
+        }
+    }
+
+    /* distance from a point to the sink's edge, zero inside it */
+    pub fn distance_to(&self, x: f32, y: f32) -> f32 {
+        match &self.shape {
+            SinkShape::Circle { center, radius } => {
+                ((x - center.x).hypot(y - center.y) - radius).max(0.0)
+            }
+            SinkShape::Rect { min, max } => {
+                let dx = (min.x - x).max(x - max.x).max(0.0);
+                let dy = (min.y - y).max(y - max.y).max(0.0);
+                dx.hypot(dy)
+            }
+        }
+    }
+}
+
+impl Simulator {
+    pub fn emitters(&self) -> &[Emitter] {
+        &self.emitters
+    }
+
+    pub fn set_emitters(&mut self, emitters: Vec<Emitter>) -> Result<(), String> {
+        for e in &emitters {
+            e.check()?;
+        }
+        self.emitters = emitters;
+        Ok(())
+    }
+
+    pub fn add_emitter(&mut self, emitter: Emitter) -> Result<usize, String> {
+        emitter.check()?;
+        self.emitters.push(emitter);
+        Ok(self.emitters.len() - 1)
+    }
+
+    pub fn remove_emitter(&mut self, index: usize) -> Option<Emitter> {
+        if index >= self.emitters.len() {
+            return None;
+        }
+        Some(self.emitters.remove(index))
+    }
+
+    pub fn emitter_near(&self, x: f32, y: f32, radius: f32) -> Option<usize> {
+        self.emitters
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (i, e.distance_to(x, y)))
+            .filter(|(_, d)| *d <= radius)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(i, _)| i)
+    }
+
+    pub fn sinks(&self) -> &[Sink] {
+        &self.sinks
+    }
+
+    pub fn set_sinks(&mut self, sinks: Vec<Sink>) {
+        self.sinks = sinks;
+    }
+
+    pub fn add_sink(&mut self, sink: Sink) -> usize {
+        self.sinks.push(sink);
+        self.sinks.len() - 1
+    }
+
+    pub fn remove_sink(&mut self, index: usize) -> Option<Sink> {
+        if index >= self.sinks.len() {
+            return None;
+        }
+        Some(self.sinks.remove(index))
+    }
+
+    pub fn sink_near(&self, x: f32, y: f32, radius: f32) -> Option<usize> {
+        self.sinks
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (i, s.distance_to(x, y)))
+            .filter(|(_, d)| *d <= radius)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(i, _)| i)
+    }
+
+    /* removes expired particles and the ones inside sinks, then lets the emitters
+    release what is due over the next step */
+    pub(super) fn update_flow(&mut self) {
+        let time = self.time();
+        let mut removed = Vec::new();
+        for p in self.particle_list.borrow().iter() {
+            if p.expires.is_some_and(|expires| expires <= time) {
+                removed.push(p.id);
+            } else if let Some(sink) = self.sinks.iter_mut().find(|s| s.contains(&p.pos)) {
+                sink.absorbed += 1;
+                removed.push(p.id);
+            }
+        }
+        if !removed.is_empty() {
+            let energy = self.energy_of(&self.particle_list.borrow(), &removed);
+            self.take_particles(&removed);
+            self.exchange_energy(energy.map(|e| -e));
+        }
+
+        if self.emitters.is_empty() {
+            return;
+        }
+        let dt = self.timestep;
+        // reseeded every step, which is cheap for SmallRng
+        let mut rng = SmallRng::seed_from_u64(
+            self.seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ self.steps as u64,
+        );
+        let mut emitted = Vec::new();
+        for emitter in self.emitters.iter_mut() {
+            emitted.extend(emitter.emit(&mut rng, time, dt));
+        }
+        // emit() only makes particles with a valid mass
+        let ids: Vec<u32> = emitted
+            .into_iter()
+            .map(|p| self.insert_particle(p))
+            .collect();
+        if !ids.is_empty() {
+            let energy = self.energy_of(&self.particle_list.borrow(), &ids);
+            self.exchange_energy(energy);
+        }
+    }
+}
@@ -0,0 +1,659 @@
+use super::util::PhysVector;
+use super::Particle;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+/* the values an expression can read about the particle being pushed */
+const INPUTS: [&str; 7] = ["x", "y", "vx", "vy", "t", "m", "q"];
+const TIME_SLOT: usize = 4;
+// register file and evaluation stack are fixed-size so evaluation never allocates
+const MAX_SLOTS: usize = 64;
+const MAX_STACK: usize = 64;
+// deepest syntax tree accepted; parsing, folding, emitting and dropping it all recurse
+// once per level, so without a limit a long run of '(' overflows the native stack
+const MAX_DEPTH: usize = 256;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Func1 {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Sqrt,
+    Exp,
+    Ln,
+    Abs,
+    Sign,
+    Floor,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Func2 {
+    Atan2,
+    Min,
+    Max,
+    Pow,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Op {
+    Const(f32),
+    Load(usize),
+    Store(usize),
+    Neg,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Call1(Func1),
+    Call2(Func2),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Node {
+    Number(f32),
+    Slot(usize),
+    Neg(Box<Node>),
+    Binary(Op, Box<Node>, Box<Node>),
+    Call1(Func1, Box<Node>),
+    Call2(Func2, Box<Node>, Box<Node>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Number(f32),
+    Ident(String),
+    Symbol(char),
+    End,
+}
+
+fn func1(name: &str) -> Option<Func1> {
+    Some(match name {
+        "sin" => Func1::Sin,
+        "cos" => Func1::Cos,
+        "tan" => Func1::Tan,
+        "asin" => Func1::Asin,
+        "acos" => Func1::Acos,
+        "atan" => Func1::Atan,
+        "sinh" => Func1::Sinh,
+        "cosh" => Func1::Cosh,
+        "tanh" => Func1::Tanh,
+        "sqrt" => Func1::Sqrt,
+        "exp" => Func1::Exp,
+        "ln" | "log" => Func1::Ln,
+        "abs" => Func1::Abs,
+        "sign" => Func1::Sign,
+        "floor" => Func1::Floor,
+        _ => return None,
+    })
+}
+
+fn func2(name: &str) -> Option<Func2> {
+    Some(match name {
+        "atan2" => Func2::Atan2,
+        "min" => Func2::Min,
+        "max" => Func2::Max,
+        "pow" => Func2::Pow,
+        _ => return None,
+    })
+}
+
+fn apply1(f: Func1, a: f32) -> f32 {
+    match f {
+        Func1::Sin => a.sin(),
+        Func1::Cos => a.cos(),
+        Func1::Tan => a.tan(),
+        Func1::Asin => a.asin(),
+        Func1::Acos => a.acos(),
+        Func1::Atan => a.atan(),
+        Func1::Sinh => a.sinh(),
+        Func1::Cosh => a.cosh(),
+        Func1::Tanh => a.tanh(),
+        Func1::Sqrt => a.sqrt(),
+        Func1::Exp => a.exp(),
+        Func1::Ln => a.ln(),
+        Func1::Abs => a.abs(),
+        Func1::Sign => {
+            if a == 0.0 {
+                0.0
+            } else {
+                a.signum()
+            }
+        }
+        Func1::Floor => a.floor(),
+    }
+}
+
+fn apply2(f: Func2, a: f32, b: f32) -> f32 {
+    match f {
+        Func2::Atan2 => a.atan2(b),
+        Func2::Min => a.min(b),
+        Func2::Max => a.max(b),
+        Func2::Pow => a.powf(b),
+    }
+}
+
+fn apply_binary(op: Op, a: f32, b: f32) -> f32 {
+    match op {
+        Op::Add => a + b,
+        Op::Sub => a - b,
+        Op::Mul => a * b,
+        Op::Div => a / b,
+        Op::Pow => a.powf(b),
+        _ => unreachable!("not a binary operator"),
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            // line breaks separate statements just like ';'
+            if c == '\n' {
+                tokens.push((Token::Symbol(';'), column));
+            }
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // exponent, as in 1e-3
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .parse::<f32>()
+                .map_err(|_| format!("column {}: bad number \"{}\"", column, text))?;
+            tokens.push((Token::Number(value), column));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((Token::Ident(chars[start..i].iter().collect()), column));
+        } else if "+-*/^(),;=".contains(c) {
+            tokens.push((Token::Symbol(c), column));
+            i += 1;
+        } else {
+            return Err(format!("column {}: unexpected character '{}'", column, c));
+        }
+    }
+    tokens.push((Token::End, chars.len() + 1));
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    slots: &'a mut Vec<String>,
+    // levels of the tree above the node being parsed
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index].0
+    }
+
+    fn column(&self) -> usize {
+        self.tokens[self.index].1
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.index].0.clone();
+        if token != Token::End {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn descend(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!(
+                "column {}: expression is nested too deeply",
+                self.column()
+            ));
+        }
+        Ok(())
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        if *self.peek() == Token::Symbol(symbol) {
+            self.next();
+            Ok(())
+        } else {
+            Err(format!("column {}: expected '{}'", self.column(), symbol))
+        }
+    }
+
+    // each operator in a chain like a + b + c adds a level above the first operand
+    fn expr(&mut self) -> Result<Node, String> {
+        let depth = self.depth;
+        let mut node = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Token::Symbol('+') => Op::Add,
+                Token::Symbol('-') => Op::Sub,
+                _ => break,
+            };
+            self.next();
+            self.descend()?;
+            node = Node::Binary(op, Box::new(node), Box::new(self.term()?));
+        }
+        self.depth = depth;
+        Ok(node)
+    }
+
+    fn term(&mut self) -> Result<Node, String> {
+        let depth = self.depth;
+        let mut node = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Token::Symbol('*') => Op::Mul,
+                Token::Symbol('/') => Op::Div,
+                _ => break,
+            };
+            self.next();
+            self.descend()?;
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+        self.depth = depth;
+        Ok(node)
+    }
+
+    // -x^2 is -(x^2), and ^ is right associative
+    fn unary(&mut self) -> Result<Node, String> {
+        self.descend()?;
+        let node = self.signed();
+        self.depth -= 1;
+        node
+    }
+
+    fn signed(&mut self) -> Result<Node, String> {
+        match self.peek() {
+            Token::Symbol('-') => {
+                self.next();
+                Ok(Node::Neg(Box::new(self.unary()?)))
+            }
+            Token::Symbol('+') => {
+                self.next();
+                self.unary()
+            }
+            _ => {
+                let base = self.atom()?;
+                if *self.peek() == Token::Symbol('^') {
+                    self.next();
+                    Ok(Node::Binary(Op::Pow, Box::new(base), Box::new(self.unary()?)))
+                } else {
+                    Ok(base)
+                }
+            }
+        }
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let column = self.column();
+        match self.next() {
+            Token::Number(value) => Ok(Node::Number(value)),
+            Token::Symbol('(') => {
+                let node = self.expr()?;
+                self.expect(')')?;
+                Ok(node)
+            }
+            Token::Ident(name) => {
+                if *self.peek() == Token::Symbol('(') {
+                    self.next();
+                    self.call(&name, column)
+                } else {
+                    match name.as_str() {
+                        "pi" => Ok(Node::Number(std::f32::consts::PI)),
+                        "e" => Ok(Node::Number(std::f32::consts::E)),
+                        _ => match self.slots.iter().position(|s| *s == name) {
+                            Some(slot) => Ok(Node::Slot(slot)),
+                            None => Err(format!("column {}: unknown variable \"{}\"", column, name)),
+                        },
+                    }
+                }
+            }
+            Token::End => Err(format!("column {}: unexpected end of expression", column)),
+            Token::Symbol(c) => Err(format!("column {}: unexpected '{}'", column, c)),
+        }
+    }
+
+    fn call(&mut self, name: &str, column: usize) -> Result<Node, String> {
+        let mut args = vec![self.expr()?];
+        while *self.peek() == Token::Symbol(',') {
+            self.next();
+            args.push(self.expr()?);
+        }
+        self.expect(')')?;
+        if let Some(f) = func1(name) {
+            if args.len() != 1 {
+                return Err(format!("column {}: {} takes 1 argument", column, name));
+            }
+            Ok(Node::Call1(f, Box::new(args.remove(0))))
+        } else if let Some(f) = func2(name) {
+            if args.len() != 2 {
+                return Err(format!("column {}: {} takes 2 arguments", column, name));
+            }
+            let b = args.remove(1);
+            let a = args.remove(0);
+            Ok(Node::Call2(f, Box::new(a), Box::new(b)))
+        } else {
+            Err(format!("column {}: unknown function \"{}\"", column, name))
+        }
+    }
+}
+
+/* evaluates constant subtrees once at compile time */
+fn fold(node: Node) -> Node {
+    match node {
+        Node::Neg(a) => match fold(*a) {
+            Node::Number(v) => Node::Number(-v),
+            a => Node::Neg(Box::new(a)),
+        },
+        Node::Binary(op, a, b) => match (fold(*a), fold(*b)) {
+            (Node::Number(a), Node::Number(b)) => Node::Number(apply_binary(op, a, b)),
+            (a, b) => Node::Binary(op, Box::new(a), Box::new(b)),
+        },
+        Node::Call1(f, a) => match fold(*a) {
+            Node::Number(v) => Node::Number(apply1(f, v)),
+            a => Node::Call1(f, Box::new(a)),
+        },
+        Node::Call2(f, a, b) => match (fold(*a), fold(*b)) {
+            (Node::Number(a), Node::Number(b)) => Node::Number(apply2(f, a, b)),
+            (a, b) => Node::Call2(f, Box::new(a), Box::new(b)),
+        },
+        other => other,
+    }
+}
+
+/* emits postfix ops, returns the stack depth the node needs */
+fn emit(node: &Node, ops: &mut Vec<Op>) -> usize {
+    match node {
+        Node::Number(v) => {
+            ops.push(Op::Const(*v));
+            1
+        }
+        Node::Slot(s) => {
+            ops.push(Op::Load(*s));
+            1
+        }
+        Node::Neg(a) => {
+            let depth = emit(a, ops);
+            ops.push(Op::Neg);
+            depth
+        }
+        Node::Call1(f, a) => {
+            let depth = emit(a, ops);
+            ops.push(Op::Call1(*f));
+            depth
+        }
+        Node::Binary(op, a, b) => {
+            let depth = emit(a, ops).max(1 + emit(b, ops));
+            ops.push(*op);
+            depth
+        }
+        Node::Call2(f, a, b) => {
+            let depth = emit(a, ops).max(1 + emit(b, ops));
+            ops.push(Op::Call2(*f));
+            depth
+        }
+    }
+}
+
+/* a force field written as statements like `k = 10; ax = -k*x; ay = -k*y + sin(t)`.
+names assigned a plain constant exactly once become tunable parameters, other assignments are
+intermediate values; ax and ay give the acceleration */
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(try_from = "ExpressionSpec", into = "ExpressionSpec")]
+pub struct FieldExpression {
+    source: String,
+    ops: Vec<Op>,
+    parameters: Vec<ExpressionParameter>,
+    slot_count: usize,
+    ax_slot: usize,
+    ay_slot: usize,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct ExpressionParameter {
+    name: String,
+    slot: usize,
+    // the value written in the source
+    default: f32,
+    value: f32,
+}
+
+/* what gets saved: the source plus any parameters changed since parsing */
+#[derive(Serialize, Deserialize)]
+pub struct ExpressionSpec {
+    source: String,
+    #[serde(default)]
+    parameters: Vec<(String, f32)>,
+}
+
+impl TryFrom<ExpressionSpec> for FieldExpression {
+    type Error = String;
+
+    fn try_from(spec: ExpressionSpec) -> Result<FieldExpression, String> {
+        let mut expression = FieldExpression::parse(&spec.source)?;
+        for (name, value) in spec.parameters {
+            expression.set_parameter(&name, value)?;
+        }
+        Ok(expression)
+    }
+}
+
+impl From<FieldExpression> for ExpressionSpec {
+    fn from(expression: FieldExpression) -> ExpressionSpec {
+        ExpressionSpec {
+            parameters: expression.parameters(),
+            source: expression.source,
+        }
+    }
+}
+
+impl FieldExpression {
+    pub fn parse(source: &str) -> Result<FieldExpression, String> {
+        let mut tokens = tokenize(source)?;
+        let mut slots: Vec<String> = INPUTS.iter().map(|s| s.to_string()).collect();
+        slots.push(String::from("ax"));
+        slots.push(String::from("ay"));
+        let ax_slot = INPUTS.len();
+        let ay_slot = INPUTS.len() + 1;
+
+        let mut ops = Vec::new();
+        let mut parameters = Vec::new();
+        let mut assigned_accel = false;
+        let mut max_depth = 0;
+
+        // drop empty statements so "a; ;b" and trailing separators are fine
+        tokens.dedup_by(|a, b| a.0 == Token::Symbol(';') && b.0 == Token::Symbol(';'));
+        let mut parser = Parser {
+            tokens,
+            index: 0,
+            slots: &mut slots,
+            depth: 0,
+        };
+        while *parser.peek() == Token::Symbol(';') {
+            parser.next();
+        }
+        // (slot, value) of every statement, in order
+        let mut statements: Vec<(usize, Node)> = Vec::new();
+        while *parser.peek() != Token::End {
+            let column = parser.column();
+            let name = match parser.next() {
+                Token::Ident(name) => name,
+                _ => return Err(format!("column {}: expected a name to assign", column)),
+            };
+            if INPUTS.contains(&name.as_str()) || name == "pi" || name == "e" {
+                return Err(format!("column {}: \"{}\" can't be assigned", column, name));
+            }
+            // <source>.weight is the weight of the whole expression source
+            if name == "weight" {
+                return Err(format!(
+                    "column {}: \"weight\" is reserved for the source weight",
+                    column
+                ));
+            }
+            parser.expect('=')?;
+            let value = fold(parser.expr()?);
+            match parser.peek() {
+                Token::Symbol(';') => {
+                    parser.next();
+                }
+                Token::End => {}
+                _ => return Err(format!("column {}: expected ';'", parser.column())),
+            }
+
+            let slot = match parser.slots.iter().position(|s| *s == name) {
+                Some(slot) => slot,
+                None => {
+                    parser.slots.push(name);
+                    parser.slots.len() - 1
+                }
+            };
+            if slot == ax_slot || slot == ay_slot {
+                assigned_accel = true;
+            }
+            statements.push((slot, value));
+        }
+
+        for (slot, value) in &statements {
+            let assignments = statements.iter().filter(|(s, _)| s == slot).count();
+            match value {
+                // a name only ever assigned one constant becomes a parameter, loaded
+                // before the first statement runs
+                Node::Number(v) if *slot != ax_slot && *slot != ay_slot && assignments == 1 => {
+                    parameters.push(ExpressionParameter {
+                        name: slots[*slot].clone(),
+                        slot: *slot,
+                        default: *v,
+                        value: *v,
+                    });
+                }
+                value => {
+                    max_depth = max_depth.max(emit(value, &mut ops));
+                    ops.push(Op::Store(*slot));
+                }
+            }
+        }
+        if !assigned_accel {
+            return Err(String::from("expression must assign ax or ay"));
+        }
+        if slots.len() > MAX_SLOTS {
+            return Err(format!("too many names, at most {} allowed", MAX_SLOTS));
+        }
+        if max_depth > MAX_STACK {
+            return Err(String::from("expression is nested too deeply"));
+        }
+        Ok(FieldExpression {
+            source: source.to_string(),
+            ops,
+            parameters,
+            slot_count: slots.len(),
+            ax_slot,
+            ay_slot,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn parameters(&self) -> Vec<(String, f32)> {
+        self.parameters
+            .iter()
+            .map(|p| (p.name.clone(), p.value))
+            .collect()
+    }
+
+    /* the values as written in the source, before any set_parameter */
+    pub fn parameter_defaults(&self) -> Vec<(String, f32)> {
+        self.parameters
+            .iter()
+            .map(|p| (p.name.clone(), p.default))
+            .collect()
+    }
+
+    pub fn set_parameter(&mut self, name: &str, value: f32) -> Result<(), String> {
+        match self.parameters.iter_mut().find(|p| p.name == name) {
+            Some(parameter) => {
+                parameter.value = value;
+                Ok(())
+            }
+            None => Err(format!("expression has no parameter \"{}\"", name)),
+        }
+    }
+
+    /* whether t appears anywhere, i.e. the field changes over time */
+    pub fn uses_time(&self) -> bool {
+        self.ops.contains(&Op::Load(TIME_SLOT))
+    }
+
+    pub fn acceleration(&self, p: &Particle, time: f32) -> PhysVector {
+        let mut slots = [0.0_f32; MAX_SLOTS];
+        slots[..INPUTS.len()].copy_from_slice(&[
+            p.pos.x, p.pos.y, p.vel.x, p.vel.y, time, p.mass, p.charge,
+        ]);
+        for parameter in &self.parameters {
+            slots[parameter.slot] = parameter.value;
+        }
+        let mut stack = [0.0_f32; MAX_STACK];
+        let mut top = 0;
+        for op in &self.ops {
+            match *op {
+                Op::Const(v) => {
+                    stack[top] = v;
+                    top += 1;
+                }
+                Op::Load(s) => {
+                    stack[top] = slots[s];
+                    top += 1;
+                }
+                Op::Store(s) => {
+                    top -= 1;
+                    slots[s] = stack[top];
+                }
+                Op::Neg => stack[top - 1] = -stack[top - 1],
+                Op::Call1(f) => stack[top - 1] = apply1(f, stack[top - 1]),
+                Op::Call2(f) => {
+                    top -= 1;
+                    stack[top - 1] = apply2(f, stack[top - 1], stack[top]);
+                }
+                op => {
+                    top -= 1;
+                    stack[top - 1] = apply_binary(op, stack[top - 1], stack[top]);
+                }
+            }
+        }
+        debug_assert!(self.slot_count <= MAX_SLOTS);
+        PhysVector {
+            x: slots[self.ax_slot],
+            y: slots[self.ay_slot],
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use super::util::PhysVector;
+use super::Particle;
+
+/* computes the acceleration of every particle in a system at the given time, in order */
+pub type AccelerationFn<'a> = dyn Fn(&[Particle], f32) -> Vec<PhysVector> + 'a;
+
+/* advances a whole particle system from `time` to `time + dt` */
+pub trait Integrator {
+    fn name(&self) -> &'static str;
+    fn step(&self, particles: &mut [Particle], time: f32, dt: f32, accel: &AccelerationFn);
+}
+
+pub struct ExplicitEuler;
+pub struct SemiImplicitEuler;
+pub struct VelocityVerlet;
+pub struct Leapfrog;
+pub struct RungeKutta4;
+
+pub const INTEGRATOR_NAMES: [&str; 5] = [
+    "Explicit Euler",
+    "Semi-Implicit Euler",
+    "Velocity Verlet",
+    "Leapfrog",
+    "RK4",
+];
+
+pub fn integrator_from_name(name: &str) -> Option<Box<dyn Integrator>> {
+    match name {
+        "Explicit Euler" => Some(Box::new(ExplicitEuler)),
+        "Semi-Implicit Euler" => Some(Box::new(SemiImplicitEuler)),
+        "Velocity Verlet" => Some(Box::new(VelocityVerlet)),
+        "Leapfrog" => Some(Box::new(Leapfrog)),
+        "RK4" => Some(Box::new(RungeKutta4)),
+        _ => None,
+    }
+}
+
+impl Integrator for ExplicitEuler {
+    fn name(&self) -> &'static str {
+        "Explicit Euler"
+    }
+
+    fn step(&self, particles: &mut [Particle], time: f32, dt: f32, accel: &AccelerationFn) {
+        let a = accel(particles, time);
+        for (p, a) in particles.iter_mut().zip(a.iter()) {
+            p.pos = &(&p.vel * dt) + &p.pos;
+            p.vel = &(a * dt) + &p.vel;
+        }
+    }
+}
+
+impl Integrator for SemiImplicitEuler {
+    fn name(&self) -> &'static str {
+        "Semi-Implicit Euler"
+    }
+
+    fn step(&self, particles: &mut [Particle], time: f32, dt: f32, accel: &AccelerationFn) {
+        let a = accel(particles, time);
+        for (p, a) in particles.iter_mut().zip(a.iter()) {
+            p.vel = &(a * dt) + &p.vel;
+            p.pos = &(&p.vel * dt) + &p.pos;
+        }
+    }
+}
+
+impl Integrator for VelocityVerlet {
+    fn name(&self) -> &'static str {
+        "Velocity Verlet"
+    }
+
+    fn step(&self, particles: &mut [Particle], time: f32, dt: f32, accel: &AccelerationFn) {
+        let a0 = accel(particles, time);
+        for (p, a) in particles.iter_mut().zip(a0.iter()) {
+            p.pos = &(&(&p.vel * dt) + &(a * (0.5 * dt * dt))) + &p.pos;
+        }
+        let a1 = accel(particles, time + dt);
+        for (p, (a0, a1)) in particles.iter_mut().zip(a0.iter().zip(a1.iter())) {
+            p.vel = &(&(a0 + a1) * (0.5 * dt)) + &p.vel;
+        }
+    }
+}
+
+// drift-kick-drift form: one force evaluation per step, at the half-step position
+impl Integrator for Leapfrog {
+    fn name(&self) -> &'static str {
+        "Leapfrog"
+    }
+
+    fn step(&self, particles: &mut [Particle], time: f32, dt: f32, accel: &AccelerationFn) {
+        for p in particles.iter_mut() {
+            p.pos = &(&p.vel * (0.5 * dt)) + &p.pos;
+        }
+        let a = accel(particles, time + 0.5 * dt);
+        for (p, a) in particles.iter_mut().zip(a.iter()) {
+            p.vel = &(a * dt) + &p.vel;
+            p.pos = &(&p.vel * (0.5 * dt)) + &p.pos;
+        }
+    }
+}
+
+impl Integrator for RungeKutta4 {
+    fn name(&self) -> &'static str {
+        "RK4"
+    }
+
+    fn step(&self, particles: &mut [Particle], time: f32, dt: f32, accel: &AccelerationFn) {
+        let start: Vec<Particle> = particles.to_vec();
+        let mut stage = start.clone();
+
+        // each stage's derivative is (velocity, acceleration) at the trial state
+        let mut k_vel: Vec<Vec<PhysVector>> = Vec::with_capacity(4);
+        let mut k_acc: Vec<Vec<PhysVector>> = Vec::with_capacity(4);
+        for (i, factor) in [0.0, 0.5, 0.5, 1.0].iter().enumerate() {
+            if i > 0 {
+                for (j, p) in stage.iter_mut().enumerate() {
+                    p.pos = &(&k_vel[i - 1][j] * (factor * dt)) + &start[j].pos;
+                    p.vel = &(&k_acc[i - 1][j] * (factor * dt)) + &start[j].vel;
+                }
+            }
+            k_vel.push(stage.iter().map(|p| p.vel.clone()).collect());
+            k_acc.push(accel(&stage, time + factor * dt));
+        }
+
+        for (j, p) in particles.iter_mut().enumerate() {
+            let dx = &(&(&k_vel[0][j] + &(&k_vel[1][j] * 2.0)) + &(&k_vel[2][j] * 2.0))
+                + &k_vel[3][j];
+            let dv = &(&(&k_acc[0][j] + &(&k_acc[1][j] * 2.0)) + &(&k_acc[2][j] * 2.0))
+                + &k_acc[3][j];
+            p.pos = &(&dx * (dt / 6.0)) + &start[j].pos;
+            p.vel = &(&dv * (dt / 6.0)) + &start[j].vel;
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use super::util::PhysVector;
+use super::Particle;
+use serde::{Deserialize, Serialize};
+
+/* forces particles exert on each other, on top of the external field */
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Interaction {
+    None,
+    // softened Newtonian gravity, attracts in proportion to both masses
+    Gravity { g: f32, softening: f32 },
+    // softened Coulomb force, like charges repel
+    Coulomb { k: f32, softening: f32 },
+}
+
+impl Interaction {
+    pub fn default_gravity() -> Interaction {
+        Interaction::Gravity {
+            g: 0.5,
+            softening: 0.5,
+        }
+    }
+
+    pub fn default_coulomb() -> Interaction {
+        Interaction::Coulomb {
+            k: 1.0,
+            softening: 0.5,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Interaction::None => "No Interaction",
+            Interaction::Gravity { .. } => "N-Body Gravity",
+            Interaction::Coulomb { .. } => "N-Body Coulomb",
+        }
+    }
+
+    pub fn softening(&self) -> f32 {
+        match *self {
+            Interaction::None => 0.0,
+            Interaction::Gravity { softening, .. } => softening,
+            Interaction::Coulomb { softening, .. } => softening,
+        }
+    }
+
+    /* the interaction "source" of a particle: its mass or its charge */
+    pub fn source_strength(&self, p: &Particle) -> f32 {
+        match *self {
+            Interaction::None => 0.0,
+            Interaction::Gravity { .. } => p.mass,
+            Interaction::Coulomb { .. } => p.charge,
+        }
+    }
+
+    /* scales source * source / r^2 into an attractive force along the separation */
+    pub fn coupling(&self) -> f32 {
+        match *self {
+            Interaction::None => 0.0,
+            Interaction::Gravity { g, .. } => g,
+            Interaction::Coulomb { k, .. } => -k,
+        }
+    }
+
+    /* force on a particle with `strength` at `pos`, from a source at `other` */
+    pub fn force_from(
+        &self,
+        pos: &PhysVector,
+        strength: f32,
+        other: &PhysVector,
+        other_strength: f32,
+    ) -> PhysVector {
+        let dx = other.x - pos.x;
+        let dy = other.y - pos.y;
+        let soft = self.softening();
+        let dist_sq = dx * dx + dy * dy + soft * soft;
+        if dist_sq == 0.0 {
+            return PhysVector { x: 0.0, y: 0.0 };
+        }
+        let mag = self.coupling() * strength * other_strength / (dist_sq * dist_sq.sqrt());
+        PhysVector {
+            x: dx * mag,
+            y: dy * mag,
+        }
+    }
+
+    /* potential energy between a particle with `strength` at `pos` and a source at `other` */
+    pub fn potential_from(
+        &self,
+        pos: &PhysVector,
+        strength: f32,
+        other: &PhysVector,
+        other_strength: f32,
+    ) -> f32 {
+        let dx = other.x - pos.x;
+        let dy = other.y - pos.y;
+        let soft = self.softening();
+        let dist = (dx * dx + dy * dy + soft * soft).sqrt();
+        if dist == 0.0 {
+            return 0.0;
+        }
+        -self.coupling() * strength * other_strength / dist
+    }
+}
+
+/* potential energy of every pair, the sum that force_from is the gradient of */
+pub fn pairwise_potential(interaction: &Interaction, particles: &[Particle]) -> f32 {
+    if *interaction == Interaction::None {
+        return 0.0;
+    }
+    let mut total = 0.0;
+    for i in 0..particles.len() {
+        let strength_i = interaction.source_strength(&particles[i]);
+        for other in &particles[(i + 1)..] {
+            total += interaction.potential_from(
+                &particles[i].pos,
+                strength_i,
+                &other.pos,
+                interaction.source_strength(other),
+            );
+        }
+    }
+    total
+}
+
+/* how pairwise forces are summed */
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Solver {
+    Direct,
+    // quadtree approximation, theta is the opening angle (0 is exact)
+    BarnesHut { theta: f32 },
+}
+
+impl Solver {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Solver::Direct => "Direct Sum",
+            Solver::BarnesHut { .. } => "Barnes-Hut",
+        }
+    }
+}
+
+/* direct O(n^2) summation, each pair visited once */
+pub fn pairwise_forces(interaction: &Interaction, particles: &[Particle]) -> Vec<PhysVector> {
+    let mut forces = vec![PhysVector { x: 0.0, y: 0.0 }; particles.len()];
+    if *interaction == Interaction::None {
+        return forces;
+    }
+    for i in 0..particles.len() {
+        let strength_i = interaction.source_strength(&particles[i]);
+        for j in (i + 1)..particles.len() {
+            let strength_j = interaction.source_strength(&particles[j]);
+            let f = interaction.force_from(
+                &particles[i].pos,
+                strength_i,
+                &particles[j].pos,
+                strength_j,
+            );
+            forces[i] = &forces[i] + &f;
+            forces[j] = &forces[j] + &(&f * -1.0);
+        }
+    }
+    forces
+}
@@ -0,0 +1,36 @@
+use super::util::PhysVector;
+use super::Particle;
+
+/* a damped spring from one particle to a point that follows the mouse, so bodies can be
+held or thrown while the simulation runs */
+#[derive(Clone)]
+pub struct MouseJoint {
+    pub particle: u32,
+    pub target: PhysVector,
+    // angular frequency of the spring in radians per second
+    pub frequency: f32,
+    // 1 is critically damped, below that the particle overshoots the cursor
+    pub damping_ratio: f32,
+}
+
+impl MouseJoint {
+    pub fn new(particle: u32, target: PhysVector) -> MouseJoint {
+        MouseJoint {
+            particle,
+            target,
+            frequency: 12.0,
+            damping_ratio: 0.7,
+        }
+    }
+
+    /* pull towards the target less the damping, per unit mass so heavy particles
+    follow the cursor as readily as light ones */
+    pub fn acceleration(&self, p: &Particle) -> PhysVector {
+        let stiffness = self.frequency * self.frequency;
+        let damping = 2.0 * self.damping_ratio * self.frequency;
+        PhysVector {
+            x: (self.target.x - p.pos.x) * stiffness - p.vel.x * damping,
+            y: (self.target.y - p.pos.y) * stiffness - p.vel.y * damping,
+        }
+    }
+}
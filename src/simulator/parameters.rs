@@ -0,0 +1,361 @@
+use serde::{Deserialize, Serialize};
+
+/* a named tunable value and the range it may be set within */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Parameter {
+    pub name: String,
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Parameter {
+    pub fn new(name: &str, value: f32, min: f32, max: f32) -> Parameter {
+        Parameter {
+            name: name.to_string(),
+            value,
+            min,
+            max,
+        }
+    }
+
+    pub fn check(&self, value: f32) -> Result<(), String> {
+        if value.is_finite() && value >= self.min && value <= self.max {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} must be between {} and {}, got {}",
+                self.name, self.min, self.max, value
+            ))
+        }
+    }
+}
+
+/* the constants of one force function */
+pub trait ParameterSet {
+    fn parameters(&self) -> Vec<Parameter>;
+    fn value_mut(&mut self, name: &str) -> Option<&mut f32>;
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GravityParams {
+    pub g: f32,
+}
+
+impl Default for GravityParams {
+    fn default() -> GravityParams {
+        GravityParams { g: 20.0 }
+    }
+}
+
+impl ParameterSet for GravityParams {
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::new("g", self.g, 0.0, 100.0)]
+    }
+
+    fn value_mut(&mut self, name: &str) -> Option<&mut f32> {
+        match name {
+            "g" => Some(&mut self.g),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GravityResistiveParams {
+    pub g: f32,
+    // drag coefficient, negative so it opposes the velocity
+    pub c: f32,
+}
+
+impl Default for GravityResistiveParams {
+    fn default() -> GravityResistiveParams {
+        GravityResistiveParams { g: 9.8, c: -0.7 }
+    }
+}
+
+impl ParameterSet for GravityResistiveParams {
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::new("g", self.g, 0.0, 50.0),
+            Parameter::new("c", self.c, -5.0, 0.0),
+        ]
+    }
+
+    fn value_mut(&mut self, name: &str) -> Option<&mut f32> {
+        match name {
+            "g" => Some(&mut self.g),
+            "c" => Some(&mut self.c),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ButterflyParams {
+    pub strength: f32,
+}
+
+impl Default for ButterflyParams {
+    fn default() -> ButterflyParams {
+        ButterflyParams { strength: 1.0 }
+    }
+}
+
+impl ParameterSet for ButterflyParams {
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::new("strength", self.strength, 0.0, 10.0)]
+    }
+
+    fn value_mut(&mut self, name: &str) -> Option<&mut f32> {
+        match name {
+            "strength" => Some(&mut self.strength),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowsXPParams {
+    // angle in radians the swirl is rotated by
+    pub pull: f32,
+}
+
+impl Default for WindowsXPParams {
+    fn default() -> WindowsXPParams {
+        WindowsXPParams { pull: 2.0 }
+    }
+}
+
+impl ParameterSet for WindowsXPParams {
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::new(
+            "pull",
+            self.pull,
+            -std::f32::consts::PI,
+            std::f32::consts::PI,
+        )]
+    }
+
+    fn value_mut(&mut self, name: &str) -> Option<&mut f32> {
+        match name {
+            "pull" => Some(&mut self.pull),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogisticParams {
+    pub k: f32,
+    // the carrying capacity, L in the logistic equation
+    pub capacity: f32,
+}
+
+impl Default for LogisticParams {
+    fn default() -> LogisticParams {
+        LogisticParams {
+            k: 0.8,
+            capacity: 15.0,
+        }
+    }
+}
+
+impl ParameterSet for LogisticParams {
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::new("k", self.k, 0.0, 5.0),
+            Parameter::new("L", self.capacity, 1.0, 30.0),
+        ]
+    }
+
+    fn value_mut(&mut self, name: &str) -> Option<&mut f32> {
+        match name {
+            "k" => Some(&mut self.k),
+            "L" => Some(&mut self.capacity),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InverseSquareParams {
+    pub strength: f32,
+}
+
+impl Default for InverseSquareParams {
+    fn default() -> InverseSquareParams {
+        InverseSquareParams { strength: 300.0 }
+    }
+}
+
+impl ParameterSet for InverseSquareParams {
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::new("strength", self.strength, 0.0, 1000.0)]
+    }
+
+    fn value_mut(&mut self, name: &str) -> Option<&mut f32> {
+        match name {
+            "strength" => Some(&mut self.strength),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HarmonicParams {
+    pub k: f32,
+}
+
+impl Default for HarmonicParams {
+    fn default() -> HarmonicParams {
+        HarmonicParams { k: 10.0 }
+    }
+}
+
+impl ParameterSet for HarmonicParams {
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::new("k", self.k, 0.0, 100.0)]
+    }
+
+    fn value_mut(&mut self, name: &str) -> Option<&mut f32> {
+        match name {
+            "k" => Some(&mut self.k),
+            _ => None,
+        }
+    }
+}
+
+/* two oppositely charged plates, each an inverse square line charge. Before scene
+version 4 each plate was 200 point charges pulling as 1/r; loading such a scene scales
+the strength by 20, which matches the old pull about 2 units from a plate, but farther
+out the field is weaker (near 7 times at the middle of the default gap), so old
+scenes with this function don't move the way they used to */
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParallelElectricParams {
+    // charge per unit length of each plate
+    pub strength: f32,
+    // distance between the positive and negative plates
+    pub separation: f32,
+    pub length: f32,
+}
+
+impl Default for ParallelElectricParams {
+    fn default() -> ParallelElectricParams {
+        ParallelElectricParams {
+            strength: 100.0,
+            separation: 40.0,
+            length: 30.0,
+        }
+    }
+}
+
+impl ParameterSet for ParallelElectricParams {
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::new("strength", self.strength, 0.0, 500.0),
+            Parameter::new("separation", self.separation, 2.0, 60.0),
+            Parameter::new("length", self.length, 1.0, 60.0),
+        ]
+    }
+
+    fn value_mut(&mut self, name: &str) -> Option<&mut f32> {
+        match name {
+            "strength" => Some(&mut self.strength),
+            "separation" => Some(&mut self.separation),
+            "length" => Some(&mut self.length),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DrivenHarmonicParams {
+    pub k: f32,
+    // velocity damping, so the motion settles into the driven response
+    pub damping: f32,
+    pub amplitude: f32,
+    // driving angular frequency
+    pub omega: f32,
+}
+
+impl Default for DrivenHarmonicParams {
+    fn default() -> DrivenHarmonicParams {
+        DrivenHarmonicParams {
+            k: 10.0,
+            damping: 0.5,
+            amplitude: 20.0,
+            omega: 3.0,
+        }
+    }
+}
+
+impl ParameterSet for DrivenHarmonicParams {
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::new("k", self.k, 0.0, 100.0),
+            Parameter::new("damping", self.damping, 0.0, 10.0),
+            Parameter::new("amplitude", self.amplitude, 0.0, 100.0),
+            Parameter::new("omega", self.omega, 0.0, 20.0),
+        ]
+    }
+
+    fn value_mut(&mut self, name: &str) -> Option<&mut f32> {
+        match name {
+            "k" => Some(&mut self.k),
+            "damping" => Some(&mut self.damping),
+            "amplitude" => Some(&mut self.amplitude),
+            "omega" => Some(&mut self.omega),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RotatingDipoleParams {
+    // charge of each pole, the positive one leads
+    pub strength: f32,
+    // distance between the poles
+    pub separation: f32,
+    // angular velocity of the dipole about the origin
+    pub omega: f32,
+}
+
+impl Default for RotatingDipoleParams {
+    fn default() -> RotatingDipoleParams {
+        RotatingDipoleParams {
+            strength: 100.0,
+            separation: 10.0,
+            omega: 0.5,
+        }
+    }
+}
+
+impl ParameterSet for RotatingDipoleParams {
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::new("strength", self.strength, 0.0, 500.0),
+            Parameter::new("separation", self.separation, 0.5, 40.0),
+            Parameter::new("omega", self.omega, -5.0, 5.0),
+        ]
+    }
+
+    fn value_mut(&mut self, name: &str) -> Option<&mut f32> {
+        match name {
+            "strength" => Some(&mut self.strength),
+            "separation" => Some(&mut self.separation),
+            "omega" => Some(&mut self.omega),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use super::util::PhysVector;
+use super::Particle;
+use serde::{Deserialize, Serialize};
+
+// keeps the field finite for particles passing through a source
+const SOFTENING: f32 = 0.5;
+
+/* a fixed object whose field acts on every particle, on top of the force functions */
+#[derive(Clone, Serialize, Deserialize)]
+pub enum FieldSource {
+    // acts on particle charge, like charges repel
+    PointCharge { pos: PhysVector, charge: f32 },
+    // attracts particles in proportion to their mass
+    PointMass { pos: PhysVector, mass: f32 },
+    // charge spread evenly along the segment from start to end
+    LineCharge {
+        start: PhysVector,
+        end: PhysVector,
+        density: f32,
+    },
+}
+
+impl FieldSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FieldSource::PointCharge { .. } => "Point Charge",
+            FieldSource::PointMass { .. } => "Point Mass",
+            FieldSource::LineCharge { .. } => "Line Charge",
+        }
+    }
+
+    /* the middle of the source, where it is grabbed from */
+    pub fn center(&self) -> PhysVector {
+        match self {
+            FieldSource::PointCharge { pos, .. } | FieldSource::PointMass { pos, .. } => {
+                pos.clone()
+            }
+            FieldSource::LineCharge { start, end, .. } => &(start + end) * 0.5,
+        }
+    }
+
+    pub fn translate(&mut self, offset: &PhysVector) {
+        match self {
+            FieldSource::PointCharge { pos, .. } | FieldSource::PointMass { pos, .. } => {
+                *pos = &*pos + offset;
+            }
+            FieldSource::LineCharge { start, end, .. } => {
+                *start = &*start + offset;
+                *end = &*end + offset;
+            }
+        }
+    }
+
+    /* distance from a point to the nearest part of the source */
+    pub fn distance_to(&self, x: f32, y: f32) -> f32 {
+        match self {
+            FieldSource::PointCharge { pos, .. } | FieldSource::PointMass { pos, .. } => {
+                ((x - pos.x).powi(2) + (y - pos.y).powi(2)).sqrt()
+            }
+            FieldSource::LineCharge { start, end, .. } => {
+                let (dx, dy) = (end.x - start.x, end.y - start.y);
+                let length_squared = dx * dx + dy * dy;
+                let t = if length_squared > 0.0 {
+                    (((x - start.x) * dx + (y - start.y) * dy) / length_squared).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                ((x - start.x - t * dx).powi(2) + (y - start.y - t * dy).powi(2)).sqrt()
+            }
+        }
+    }
+
+    /* force on a particle: charges and line charges push on its charge, masses pull
+    on its mass */
+    pub fn force_on(&self, p: &Particle) -> PhysVector {
+        match self {
+            FieldSource::PointCharge { pos, charge } => {
+                &point_field(pos, &p.pos) * (charge * p.charge)
+            }
+            FieldSource::PointMass { pos, mass } => &point_field(pos, &p.pos) * (-mass * p.mass),
+            FieldSource::LineCharge {
+                start,
+                end,
+                density,
+            } => &line_field(start, end, &p.pos) * (density * p.charge),
+        }
+    }
+
+    /* potential energy of a particle in the source's field */
+    pub fn potential_on(&self, p: &Particle) -> f32 {
+        match self {
+            FieldSource::PointCharge { pos, charge } => {
+                charge * p.charge * point_potential(pos, &p.pos)
+            }
+            FieldSource::PointMass { pos, mass } => -mass * p.mass * point_potential(pos, &p.pos),
+            FieldSource::LineCharge {
+                start,
+                end,
+                density,
+            } => density * p.charge * line_potential(start, end, &p.pos),
+        }
+    }
+}
+
+fn point_potential(source: &PhysVector, at: &PhysVector) -> f32 {
+    let dx = at.x - source.x;
+    let dy = at.y - source.y;
+    1.0 / (dx * dx + dy * dy + SOFTENING * SOFTENING).sqrt()
+}
+
+/* softened inverse square field of a unit source at `source`, pointing away from it */
+fn point_field(source: &PhysVector, at: &PhysVector) -> PhysVector {
+    let dx = at.x - source.x;
+    let dy = at.y - source.y;
+    let r2 = dx * dx + dy * dy + SOFTENING * SOFTENING;
+    let scale = 1.0 / (r2 * r2.sqrt());
+    PhysVector {
+        x: dx * scale,
+        y: dy * scale,
+    }
+}
+
+/* inverse square field of a segment with unit charge per length, integrated in closed
+form: along the segment direction u, with a and b the ends' offsets from the foot of the
+perpendicular and h the perpendicular distance,
+    E_perp = h [t / (h^2 sqrt(h^2 + t^2))] from a to b
+    E_along = [1 / sqrt(h^2 + t^2)] from a to b
+h^2 is softened, which keeps the field finite on the segment itself */
+pub fn line_field(start: &PhysVector, end: &PhysVector, at: &PhysVector) -> PhysVector {
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length == 0.0 {
+        return PhysVector { x: 0.0, y: 0.0 };
+    }
+    let u = PhysVector {
+        x: dx / length,
+        y: dy / length,
+    };
+    let (rx, ry) = (at.x - start.x, at.y - start.y);
+    let along = rx * u.x + ry * u.y;
+    // perpendicular offset of the point from the line, unnormalised
+    let (px, py) = (rx - along * u.x, ry - along * u.y);
+    let h2 = px * px + py * py + SOFTENING * SOFTENING;
+
+    let a = -along;
+    let b = length - along;
+    let root_a = (h2 + a * a).sqrt();
+    let root_b = (h2 + b * b).sqrt();
+    let perpendicular = (b / root_b - a / root_a) / h2;
+    let parallel = 1.0 / root_b - 1.0 / root_a;
+    PhysVector {
+        x: px * perpendicular + u.x * parallel,
+        y: py * perpendicular + u.y * parallel,
+    }
+}
+
+/* potential of the same segment, asinh(t / h) from a to b, whose negative gradient
+is line_field */
+pub fn line_potential(start: &PhysVector, end: &PhysVector, at: &PhysVector) -> f32 {
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length == 0.0 {
+        return 0.0;
+    }
+    let (rx, ry) = (at.x - start.x, at.y - start.y);
+    let along = (rx * dx + ry * dy) / length;
+    let h = (rx * rx + ry * ry - along * along + SOFTENING * SOFTENING)
+        .max(SOFTENING * SOFTENING)
+        .sqrt();
+    ((length - along) / h).asinh() - (-along / h).asinh()
+}
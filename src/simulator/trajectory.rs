@@ -0,0 +1,278 @@
+use super::util::PhysVector;
+use super::Simulator;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+
+// binary files start with this, followed by a little-endian u32 format version
+const BINARY_MAGIC: &[u8; 4] = b"SPTR";
+const BINARY_VERSION: u32 = 1;
+const CSV_HEADER: &str = "time,id,x,y,vx,vy";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TrajectoryFormat {
+    // one row per particle per recorded frame, a frame with no particles is a row
+    // with only the time filled in
+    Csv,
+    // magic + version header, then per frame: time (f32), particle count (u32)
+    // and for each particle id (u32) followed by x, y, vx, vy (f32), all little-endian
+    Binary,
+}
+
+impl TrajectoryFormat {
+    /* .csv is text, anything else binary */
+    pub fn from_path(path: &str) -> TrajectoryFormat {
+        if path.to_lowercase().ends_with(".csv") {
+            TrajectoryFormat::Csv
+        } else {
+            TrajectoryFormat::Binary
+        }
+    }
+}
+
+/* one particle's state within a frame */
+#[derive(Clone)]
+pub struct Sample {
+    pub id: u32,
+    pub pos: PhysVector,
+    pub vel: PhysVector,
+}
+
+#[derive(Clone)]
+pub struct Frame {
+    pub time: f32,
+    pub samples: Vec<Sample>,
+}
+
+/* writes a frame every `interval` simulator steps, counted from the first step it
+was asked to record */
+pub struct TrajectoryRecorder {
+    writer: Box<dyn Write>,
+    format: TrajectoryFormat,
+    interval: usize,
+    // a loaded scene can start anywhere, so steps count from here
+    start_step: Option<usize>,
+    last_step: Option<usize>,
+    frames: usize,
+}
+
+impl TrajectoryRecorder {
+    pub fn new(
+        writer: Box<dyn Write>,
+        format: TrajectoryFormat,
+        interval: usize,
+    ) -> Result<TrajectoryRecorder, String> {
+        let mut recorder = TrajectoryRecorder {
+            writer,
+            format,
+            interval: interval.max(1),
+            start_step: None,
+            last_step: None,
+            frames: 0,
+        };
+        match format {
+            TrajectoryFormat::Csv => writeln!(recorder.writer, "{}", CSV_HEADER),
+            TrajectoryFormat::Binary => recorder
+                .writer
+                .write_all(BINARY_MAGIC)
+                .and_then(|_| recorder.writer.write_all(&BINARY_VERSION.to_le_bytes())),
+        }
+        .map_err(|e| e.to_string())?;
+        Ok(recorder)
+    }
+
+    pub fn create(
+        path: &str,
+        format: TrajectoryFormat,
+        interval: usize,
+    ) -> Result<TrajectoryRecorder, String> {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+        TrajectoryRecorder::new(Box::new(BufWriter::new(file)), format, interval)
+    }
+
+    pub fn frames_written(&self) -> usize {
+        self.frames
+    }
+
+    /* records the simulator if it is a multiple of the interval past the first
+    recorded step and that step hasn't been written yet */
+    pub fn record(&mut self, sim: &Simulator) -> Result<(), String> {
+        let step = sim.steps();
+        let start = *self.start_step.get_or_insert(step);
+        let on_interval = step >= start && (step - start).is_multiple_of(self.interval);
+        if !on_interval || self.last_step == Some(step) {
+            return Ok(());
+        }
+        self.write_frame(sim)?;
+        self.last_step = Some(step);
+        Ok(())
+    }
+
+    /* unconditionally writes the current state */
+    pub fn write_frame(&mut self, sim: &Simulator) -> Result<(), String> {
+        let time = sim.time();
+        let particles = sim.particle_list.borrow();
+        let result = match self.format {
+            TrajectoryFormat::Csv if particles.is_empty() => {
+                writeln!(self.writer, "{},,,,,", time)
+            }
+            TrajectoryFormat::Csv => particles.iter().try_for_each(|p| {
+                writeln!(
+                    self.writer,
+                    "{},{},{},{},{},{}",
+                    time, p.id, p.pos.x, p.pos.y, p.vel.x, p.vel.y
+                )
+            }),
+            TrajectoryFormat::Binary => {
+                let mut bytes = Vec::with_capacity(8 + particles.len() * 20);
+                bytes.extend_from_slice(&time.to_le_bytes());
+                bytes.extend_from_slice(&(particles.len() as u32).to_le_bytes());
+                for p in particles.iter() {
+                    bytes.extend_from_slice(&p.id.to_le_bytes());
+                    for v in &[p.pos.x, p.pos.y, p.vel.x, p.vel.y] {
+                        bytes.extend_from_slice(&v.to_le_bytes());
+                    }
+                }
+                self.writer.write_all(&bytes)
+            }
+        };
+        result.map_err(|e| e.to_string())?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        self.writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+/* reads either format back, telling them apart by the binary magic */
+pub fn read_trajectory(path: &str) -> Result<Vec<Frame>, String> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_end(&mut bytes))
+        .map_err(|e| format!("{}: {}", path, e))?;
+    if bytes.starts_with(BINARY_MAGIC) {
+        read_binary(&bytes)
+    } else {
+        read_csv(BufReader::new(&bytes[..]))
+    }
+    .map_err(|e| format!("{}: {}", path, e))
+}
+
+pub fn read_csv<R: BufRead>(reader: R) -> Result<Vec<Frame>, String> {
+    let mut frames: Vec<Frame> = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if number == 0 && line.trim() == CSV_HEADER {
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        if fields.len() != 6 {
+            return Err(format!("line {}: expected 6 fields", number + 1));
+        }
+        let parse = |i: usize| -> Result<f32, String> {
+            fields[i]
+                .parse::<f32>()
+                .map_err(|e| format!("line {}: {}", number + 1, e))
+        };
+        let time = parse(0)?;
+        if fields[1..].iter().all(|f| f.is_empty()) {
+            frames.push(Frame {
+                time,
+                samples: Vec::new(),
+            });
+            continue;
+        }
+        let sample = Sample {
+            id: fields[1]
+                .parse::<u32>()
+                .map_err(|e| format!("line {}: {}", number + 1, e))?,
+            pos: PhysVector {
+                x: parse(2)?,
+                y: parse(3)?,
+            },
+            vel: PhysVector {
+                x: parse(4)?,
+                y: parse(5)?,
+            },
+        };
+        match frames.last_mut() {
+            Some(frame) if frame.time == time => frame.samples.push(sample),
+            _ => frames.push(Frame {
+                time,
+                samples: vec![sample],
+            }),
+        }
+    }
+    Ok(frames)
+}
+
+/* walks a byte slice, failing cleanly on truncated data */
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.cursor + len > self.bytes.len() {
+            return Err(String::from("unexpected end of trajectory data"));
+        }
+        let slice = &self.bytes[self.cursor..self.cursor + len];
+        self.cursor += len;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        let b = self.take(4)?;
+        Ok(f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn at_end(&self) -> bool {
+        self.cursor >= self.bytes.len()
+    }
+}
+
+pub fn read_binary(bytes: &[u8]) -> Result<Vec<Frame>, String> {
+    let mut reader = ByteReader { bytes, cursor: 0 };
+    if reader.take(4)? != BINARY_MAGIC {
+        return Err(String::from("not a binary trajectory"));
+    }
+    let version = reader.u32()?;
+    if version > BINARY_VERSION {
+        return Err(format!(
+            "trajectory version {} is newer than the supported version {}",
+            version, BINARY_VERSION
+        ));
+    }
+
+    let mut frames = Vec::new();
+    while !reader.at_end() {
+        let time = reader.f32()?;
+        let count = reader.u32()? as usize;
+        let mut samples = Vec::with_capacity(count.min(bytes.len() / 20));
+        for _ in 0..count {
+            samples.push(Sample {
+                id: reader.u32()?,
+                pos: PhysVector {
+                    x: reader.f32()?,
+                    y: reader.f32()?,
+                },
+                vel: PhysVector {
+                    x: reader.f32()?,
+                    y: reader.f32()?,
+                },
+            });
+        }
+        frames.push(Frame { time, samples });
+    }
+    Ok(frames)
+}
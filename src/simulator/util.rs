@@ -1,7 +1,8 @@
+use serde::{Deserialize, Serialize};
 use std::ops;
 
 /* A physics vector */
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PhysVector {
     pub x: f32,
     pub y: f32,
@@ -1,9 +1,9 @@
 use gl;
 use crate::gl_render;
-use crate::simulator::Simulator;
+use crate::simulator::{Particle, Simulator};
 use std::rc::Rc;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 pub struct Character {
     pub texture_id: gl::types::GLuint,
@@ -14,22 +14,66 @@ pub struct Character {
     pub advance: i64
 }
 
+/* what the particle view plots: positions, or one coordinate against its velocity */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ViewMode {
+    Space,
+    PhaseX,
+    PhaseY,
+}
+
+impl ViewMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ViewMode::Space => "x-y",
+            ViewMode::PhaseX => "x-vx",
+            ViewMode::PhaseY => "y-vy",
+        }
+    }
+}
+
 pub struct WindowData {
     pub gl: gl::Gl,
     pub x_range: f32,
     pub y_range: f32,
+    // window size in pixels, the view widens along the longer side
+    pub screen_size: (f32, f32),
     pub gridline_vbo: gl::types::GLuint,
     pub gridline_vao: gl::types::GLuint,
     pub gridline_program: gl_render::Program,
     pub gridline_vec: Vec<f32>,
+    // camera transform the gridlines were laid out for
+    pub gridline_camera: [f32; 4],
     pub vector_vbo: [gl::types::GLuint; 3],
     pub vector_vao: gl::types::GLuint,
     pub vector_program: gl_render::Program,
     pub vector_coord_storage: Vec<f32>,
     pub vector_color_storage: Vec<f32>,
     pub vectors_require_update: bool,
+    // the simulator field revision the overlay was built from
+    pub vector_revision: u64,
+    pub vector_camera: [f32; 4],
     pub vec_range: f32,
+    pub source_vbo: gl::types::GLuint,
+    pub source_vao: gl::types::GLuint,
+    pub source_program: gl_render::Program,
+    pub trail_vbo: gl::types::GLuint,
+    pub trail_vao: gl::types::GLuint,
+    pub trail_program: gl_render::Program,
+    // recent view coordinates of each particle by id, oldest first
+    pub trails: HashMap<u32, VecDeque<(f32, f32)>>,
+    // points kept per trail, 0 turns trails off
+    pub trail_length: usize,
+    // simulator step the trails were last extended at
+    pub trail_step: usize,
     pub character_map: HashMap<u8, Character>, 
+    pub view_mode: ViewMode,
+    // view coordinates at the middle of the screen
+    pub camera_center: (f32, f32),
+    // 1 shows x_range either side of the center along the shorter side, larger zooms in
+    pub zoom: f32,
+    // half height of the velocity axis in phase space, grows to fit the fastest particle
+    pub velocity_range: f32,
 
     pub simulator: Rc<RefCell<Simulator>>
 }
@@ -38,24 +82,152 @@ impl WindowData {
     pub fn new(gl: &gl::Gl, x_range: f32, y_range: f32, sim: Rc<RefCell<Simulator>>) -> WindowData {
         WindowData {
             gl: gl.clone(),
-            x_range: x_range,
-            y_range: y_range,
+            x_range,
+            y_range,
+            screen_size: (1.0, 1.0),
             gridline_vbo: 0,
-            gridline_program: gl_render::Program::blank_program(&gl),
+            gridline_program: gl_render::Program::blank_program(gl),
             gridline_vao: 0,
             gridline_vec: Vec::new(),
+            gridline_camera: [0.0; 4],
 
             vector_vbo: [0, 0, 0],
-            vector_program: gl_render::Program::blank_program(&gl),
+            vector_program: gl_render::Program::blank_program(gl),
             vector_vao: 0,
             vector_coord_storage: Vec::new(),
             vector_color_storage: Vec::new(),
             vec_range: 0.0,
             vectors_require_update: false,
+            vector_revision: 0,
+            vector_camera: [0.0; 4],
+            source_vbo: 0,
+            source_vao: 0,
+            source_program: gl_render::Program::blank_program(gl),
+
+            trail_vbo: 0,
+            trail_vao: 0,
+            trail_program: gl_render::Program::blank_program(gl),
+            trails: HashMap::new(),
+            trail_length: 0,
+            trail_step: 0,
 
             character_map: HashMap::new(),
+            view_mode: ViewMode::Space,
+            camera_center: (0.0, 0.0),
+            zoom: 1.0,
+            velocity_range: y_range,
 
             simulator: sim
         }
     }
-}
\ No newline at end of file
+
+    /* the particle's coordinates on the view's axes, position or phase */
+    pub fn view_coordinates(&self, p: &Particle) -> (f32, f32) {
+        let pos = p.get_pos();
+        let vel = p.get_vel();
+        match self.view_mode {
+            ViewMode::Space => (pos.x, pos.y),
+            ViewMode::PhaseX => (pos.x, vel.x),
+            ViewMode::PhaseY => (pos.y, vel.y),
+        }
+    }
+
+    /* center and scale taking view coordinates to normalized device coordinates,
+    screen = (view - center) * scale, as passed to the shaders' camera uniform */
+    pub fn camera_transform(&self) -> [f32; 4] {
+        let (range_a, range_b) = match self.view_mode {
+            ViewMode::Space => (self.x_range, self.y_range),
+            ViewMode::PhaseX => (self.x_range, self.velocity_range),
+            ViewMode::PhaseY => (self.y_range, self.velocity_range),
+        };
+        let (width, height) = self.screen_size;
+        let (stretch_a, stretch_b) = if width >= height {
+            (width / height, 1.0)
+        } else {
+            (1.0, height / width)
+        };
+        [
+            self.camera_center.0,
+            self.camera_center.1,
+            self.zoom / (range_a * stretch_a),
+            self.zoom / (range_b * stretch_b),
+        ]
+    }
+
+    /* view coordinates to normalized device coordinates */
+    pub fn to_screen(&self, a: f32, b: f32) -> (f32, f32) {
+        let t = self.camera_transform();
+        ((a - t[0]) * t[2], (b - t[1]) * t[3])
+    }
+
+    /* a window pixel, origin at the top left, to normalized device coordinates */
+    pub fn pixel_to_screen(&self, x: i32, y: i32) -> (f32, f32) {
+        (
+            x as f32 * 2.0 / self.screen_size.0 - 1.0,
+            -(y as f32 * 2.0 / self.screen_size.1 - 1.0),
+        )
+    }
+
+    /* normalized device coordinates, such as the cursor, to view coordinates */
+    pub fn from_screen(&self, x: f32, y: f32) -> (f32, f32) {
+        let t = self.camera_transform();
+        (x / t[2] + t[0], y / t[3] + t[1])
+    }
+
+    /* the lower left and upper right corners of the screen in view coordinates */
+    pub fn visible_extent(&self) -> ((f32, f32), (f32, f32)) {
+        (self.from_screen(-1.0, -1.0), self.from_screen(1.0, 1.0))
+    }
+
+    /* zooms by factor keeping the point under (x, y) in place */
+    pub fn zoom_at(&mut self, x: f32, y: f32, factor: f32) {
+        let before = self.from_screen(x, y);
+        self.zoom = (self.zoom * factor).clamp(0.01, 100.0);
+        let after = self.from_screen(x, y);
+        self.camera_center.0 += before.0 - after.0;
+        self.camera_center.1 += before.1 - after.1;
+    }
+
+    /* moves the view so the scene follows a drag of (dx, dy) in device coordinates */
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let t = self.camera_transform();
+        self.camera_center.0 -= dx / t[2];
+        self.camera_center.1 -= dy / t[3];
+    }
+
+    pub fn reset_camera(&mut self) {
+        self.camera_center = (0.0, 0.0);
+        self.zoom = 1.0;
+    }
+
+    /* where a particle is drawn, in normalized device coordinates */
+    pub fn project(&self, p: &Particle) -> (f32, f32) {
+        let (a, b) = self.view_coordinates(p);
+        self.to_screen(a, b)
+    }
+
+    pub fn set_view_mode(&mut self, mode: ViewMode) {
+        self.view_mode = mode;
+        self.velocity_range = self.y_range;
+        self.reset_camera();
+        self.trails.clear();
+    }
+
+    /* widens the velocity axis so every particle stays on screen */
+    pub fn fit_velocity_range(&mut self) {
+        let fastest = self
+            .simulator
+            .borrow()
+            .particle_list
+            .borrow()
+            .iter()
+            .map(|p| match self.view_mode {
+                ViewMode::PhaseX => p.get_vel().x.abs(),
+                _ => p.get_vel().y.abs(),
+            })
+            .fold(0.0, f32::max);
+        if fastest.is_finite() && fastest * 1.1 > self.velocity_range {
+            self.velocity_range = fastest * 1.1;
+        }
+    }
+}
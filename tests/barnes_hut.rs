@@ -0,0 +1,128 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sdl_physics::simulator::barnes_hut::{barnes_hut_forces, barnes_hut_potential};
+use sdl_physics::simulator::interaction::{
+    pairwise_forces, pairwise_potential, Interaction, Solver,
+};
+use sdl_physics::simulator::util::PhysVector;
+use sdl_physics::simulator::{Particle, SimulateFunction, Simulator};
+
+fn random_particles(count: usize, charged: bool) -> Vec<Particle> {
+    let mut rng = StdRng::seed_from_u64(7);
+    (0..count)
+        .map(|_| {
+            let charge = if charged && rng.gen::<bool>() { -1.0 } else { 1.0 };
+            Particle::new(rng.gen_range(-30.0, 30.0), rng.gen_range(-30.0, 30.0), 0.0, 0.0)
+                .with_mass(rng.gen_range(0.5, 5.0))
+                .with_charge(charge)
+        })
+        .collect()
+}
+
+fn accelerations(
+    particles: &[Particle],
+    interaction: Interaction,
+    solver: Solver,
+) -> Vec<PhysVector> {
+    let mut sim = Simulator::new(1.0 / 120.0);
+    sim.set_function(SimulateFunction::NoForce);
+    sim.set_interaction(interaction);
+    sim.set_solver(solver);
+    sim.accelerations(particles, 0.0)
+}
+
+fn distance(a: &PhysVector, b: &PhysVector) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+fn rms(vectors: &[PhysVector]) -> f32 {
+    (vectors.iter().map(|v| v.x * v.x + v.y * v.y).sum::<f32>() / vectors.len() as f32).sqrt()
+}
+
+fn rms_error(expected: &[PhysVector], actual: &[PhysVector]) -> f32 {
+    let errors: Vec<PhysVector> = expected
+        .iter()
+        .zip(actual.iter())
+        .map(|(e, a)| PhysVector {
+            x: e.x - a.x,
+            y: e.y - a.y,
+        })
+        .collect();
+    rms(&errors)
+}
+
+#[test]
+fn barnes_hut_matches_brute_force_gravity() {
+    let particles = random_particles(500, false);
+    let interaction = Interaction::default_gravity();
+    let direct = accelerations(&particles, interaction, Solver::Direct);
+    let approx = accelerations(&particles, interaction, Solver::BarnesHut { theta: 0.3 });
+
+    let scale = rms(&direct);
+    for (d, a) in direct.iter().zip(approx.iter()) {
+        let magnitude = (d.x * d.x + d.y * d.y).sqrt();
+        assert!(
+            distance(d, a) <= 0.05 * magnitude + 0.02 * scale,
+            "barnes-hut {:?} too far from direct {:?}",
+            (a.x, a.y),
+            (d.x, d.y)
+        );
+    }
+
+    let coarse = accelerations(&particles, interaction, Solver::BarnesHut { theta: 0.5 });
+    assert!(rms_error(&direct, &coarse) <= 0.02 * scale);
+}
+
+#[test]
+fn barnes_hut_matches_brute_force_coulomb() {
+    let particles = random_particles(500, true);
+    let interaction = Interaction::default_coulomb();
+    let direct = accelerations(&particles, interaction, Solver::Direct);
+    let approx = accelerations(&particles, interaction, Solver::BarnesHut { theta: 0.5 });
+
+    assert!(rms_error(&direct, &approx) <= 0.05 * rms(&direct));
+}
+
+#[test]
+fn zero_opening_angle_is_exact() {
+    let particles = random_particles(200, false);
+    let interaction = Interaction::default_gravity();
+    let direct = pairwise_forces(&interaction, &particles);
+    let exact = barnes_hut_forces(&interaction, &particles, 0.0);
+
+    for (d, a) in direct.iter().zip(exact.iter()) {
+        let magnitude = (d.x * d.x + d.y * d.y).sqrt();
+        assert!(distance(d, a) <= 1e-3 * magnitude.max(1.0));
+    }
+}
+
+/* sum of |U| over all pairs, what the error is measured against when charges cancel */
+fn potential_scale(interaction: &Interaction, particles: &[Particle]) -> f32 {
+    let mut total = 0.0;
+    for (i, p) in particles.iter().enumerate() {
+        for q in &particles[i + 1..] {
+            total += interaction
+                .potential_from(&p.get_pos(), p.get_charge(), &q.get_pos(), q.get_charge())
+                .abs();
+        }
+    }
+    total
+}
+
+#[test]
+fn barnes_hut_potential_matches_brute_force() {
+    let particles = random_particles(500, false);
+    let gravity = Interaction::default_gravity();
+    let direct = pairwise_potential(&gravity, &particles);
+    let approx = barnes_hut_potential(&gravity, &particles, 0.5);
+    assert!((approx - direct).abs() <= 0.01 * direct.abs(), "{} vs {}", approx, direct);
+    let exact = barnes_hut_potential(&gravity, &particles, 0.0);
+    assert!((exact - direct).abs() <= 1e-4 * direct.abs(), "{} vs {}", exact, direct);
+
+    let particles = random_particles(500, true);
+    let coulomb = Interaction::default_coulomb();
+    let direct = pairwise_potential(&coulomb, &particles);
+    let approx = barnes_hut_potential(&coulomb, &particles, 0.5);
+    let scale = potential_scale(&coulomb, &particles);
+    assert!((approx - direct).abs() <= 0.01 * scale, "{} vs {}", approx, direct);
+}
@@ -0,0 +1,109 @@
+use sdl_physics::simulator::boundary::{Boundary, BoundaryMode};
+use sdl_physics::simulator::{Particle, Simulator};
+
+// (x, y, vx, vy) of each particle
+fn state(particles: &[Particle]) -> Vec<(f32, f32, f32, f32)> {
+    particles
+        .iter()
+        .map(|p| {
+            let (pos, vel) = (p.get_pos(), p.get_vel());
+            (pos.x, pos.y, vel.x, vel.y)
+        })
+        .collect()
+}
+
+/* one particle inside the box [-10, 10] x [-5, 5] and one past each wall */
+fn particles() -> Vec<Particle> {
+    vec![
+        Particle::new(1.0, 2.0, 3.0, -4.0),
+        Particle::new(12.0, 0.0, 2.0, 1.0),
+        Particle::new(-11.0, 0.0, -2.0, 0.0),
+        Particle::new(0.0, 6.0, 0.0, 3.0),
+        Particle::new(0.0, -5.5, 1.0, -3.0),
+    ]
+}
+
+fn apply(mode: BoundaryMode) -> Vec<(f32, f32, f32, f32)> {
+    let mut particles = particles();
+    Boundary::centered(10.0, 5.0, mode).apply(&mut particles);
+    state(&particles)
+}
+
+#[test]
+fn elastic_walls_mirror_the_overshoot() {
+    assert_eq!(
+        apply(BoundaryMode::Reflect { restitution: 1.0 }),
+        vec![
+            (1.0, 2.0, 3.0, -4.0),
+            (8.0, 0.0, -2.0, 1.0),
+            (-9.0, 0.0, 2.0, 0.0),
+            (0.0, 4.0, 0.0, -3.0),
+            (0.0, -4.5, 1.0, 3.0),
+        ]
+    );
+}
+
+#[test]
+fn inelastic_walls_flip_and_scale_the_velocity() {
+    assert_eq!(
+        apply(BoundaryMode::Reflect { restitution: 0.5 }),
+        vec![
+            (1.0, 2.0, 3.0, -4.0),
+            (9.0, 0.0, -1.0, 1.0),
+            (-9.5, 0.0, 1.0, 0.0),
+            (0.0, 4.5, 0.0, -1.5),
+            (0.0, -4.75, 1.0, 1.5),
+        ]
+    );
+    // a dead wall stops the particle on it
+    let stopped = apply(BoundaryMode::Reflect { restitution: 0.0 });
+    assert_eq!(stopped[1], (10.0, 0.0, 0.0, 1.0));
+}
+
+#[test]
+fn wrapping_comes_back_in_the_other_side() {
+    assert_eq!(
+        apply(BoundaryMode::Wrap),
+        vec![
+            (1.0, 2.0, 3.0, -4.0),
+            (-8.0, 0.0, 2.0, 1.0),
+            (9.0, 0.0, -2.0, 0.0),
+            (0.0, -4.0, 0.0, 3.0),
+            (0.0, 4.5, 1.0, -3.0),
+        ]
+    );
+    // several widths past a wall still lands inside
+    let mut far = vec![Particle::new(-47.0, 23.0, 0.0, 0.0)];
+    Boundary::centered(10.0, 5.0, BoundaryMode::Wrap).apply(&mut far);
+    assert_eq!(state(&far), vec![(-7.0, 3.0, 0.0, 0.0)]);
+}
+
+#[test]
+fn absorbing_walls_remove_what_leaves() {
+    assert_eq!(apply(BoundaryMode::Absorb), vec![(1.0, 2.0, 3.0, -4.0)]);
+    assert_eq!(apply(BoundaryMode::Open), state(&particles()));
+}
+
+#[test]
+fn cycling_the_mode_keeps_the_rectangle() {
+    let mut sim = Simulator::new(0.01);
+    let rectangle = Boundary {
+        x_min: -3.0,
+        x_max: 7.0,
+        y_min: 1.0,
+        y_max: 2.0,
+        mode: BoundaryMode::Open,
+    };
+    sim.set_boundary(rectangle);
+    let mut names = Vec::new();
+    for _ in 0..4 {
+        sim.cycle_boundary_mode();
+        let boundary = sim.boundary();
+        names.push(boundary.mode.name());
+        assert_eq!(
+            (boundary.x_min, boundary.x_max, boundary.y_min, boundary.y_max),
+            (-3.0, 7.0, 1.0, 2.0)
+        );
+    }
+    assert_eq!(names, vec!["Reflect", "Wrap", "Absorb", "Open"]);
+}
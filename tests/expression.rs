@@ -0,0 +1,140 @@
+use sdl_physics::simulator::expression::FieldExpression;
+use sdl_physics::simulator::Particle;
+
+// x = 2, y = 3, vx = -1, vy = 0.5, m = 2, q = -1
+fn particle() -> Particle {
+    Particle::new(2.0, 3.0, -1.0, 0.5)
+        .with_mass(2.0)
+        .with_charge(-1.0)
+}
+
+/* the x acceleration of `ax = <text>` at the test particle and time 1.5 */
+fn ax(text: &str) -> f32 {
+    let expression = FieldExpression::parse(&format!("ax = {}", text)).unwrap();
+    expression.acceleration(&particle(), 1.5).x
+}
+
+fn error(text: &str) -> String {
+    FieldExpression::parse(text).err().unwrap()
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() <= 1e-5 * b.abs().max(1.0)
+}
+
+#[test]
+fn inputs() {
+    assert_eq!(ax("x + y + vx + vy"), 4.5);
+    assert_eq!(ax("t * m * q"), -3.0);
+    let expression = FieldExpression::parse("ax = x; ay = y").unwrap();
+    let a = expression.acceleration(&particle(), 0.0);
+    assert_eq!((a.x, a.y), (2.0, 3.0));
+}
+
+#[test]
+fn precedence() {
+    assert_eq!(ax("1 + x * 3 ^ 2"), 19.0);
+    assert_eq!(ax("(1 + x) * 3"), 9.0);
+    assert_eq!(ax("x ^ 3 ^ 2"), 512.0);
+    assert_eq!(ax("16 / x / 2"), 4.0);
+    assert_eq!(ax("5 - x - 1"), 2.0);
+    assert_eq!(ax("y - x * 2 + 1"), 0.0);
+}
+
+#[test]
+fn unary_minus() {
+    assert_eq!(ax("-x ^ 2"), -4.0);
+    assert_eq!(ax("(-x) ^ 2"), 4.0);
+    assert_eq!(ax("--x"), 2.0);
+    assert_eq!(ax("3 - -x"), 5.0);
+    assert_eq!(ax("-x * -y"), 6.0);
+    assert_eq!(ax("+x"), 2.0);
+}
+
+#[test]
+fn functions_and_constants() {
+    assert!(close(ax("sin(pi / 2)"), 1.0));
+    assert!(close(ax("cos(0 * x)"), 1.0));
+    assert!(close(ax("atan2(x, x)"), std::f32::consts::FRAC_PI_4));
+    assert!(close(ax("ln(e ^ x)"), 2.0));
+    assert_eq!(ax("sqrt(x * 8)"), 4.0);
+    assert_eq!(ax("abs(vx)"), 1.0);
+    assert_eq!(ax("sign(vx) + sign(0 * x)"), -1.0);
+    assert_eq!(ax("min(x, y) + max(x, y)"), 5.0);
+    assert_eq!(ax("pow(x, 3)"), 8.0);
+    assert_eq!(ax("floor(vy)"), 0.0);
+    assert_eq!(ax("1e-3 * 2e3 + 0.5"), 2.5);
+}
+
+#[test]
+fn parse_errors_report_columns() {
+    assert_eq!(error("ax = x +"), "column 9: unexpected end of expression");
+    assert_eq!(error("ax = foo(x)"), "column 6: unknown function \"foo\"");
+    assert_eq!(error("ax = y2"), "column 6: unknown variable \"y2\"");
+    assert_eq!(error("ax = 1 $ 2"), "column 8: unexpected character '$'");
+    assert_eq!(error("ax = (x"), "column 8: expected ')'");
+    assert_eq!(error("ax = sin(1, x)"), "column 6: sin takes 1 argument");
+    assert_eq!(error("ax = min(x)"), "column 6: min takes 2 arguments");
+    assert_eq!(error("ax = x y"), "column 8: expected ';'");
+    assert_eq!(error("ax = 1; x = 2"), "column 9: \"x\" can't be assigned");
+    assert_eq!(error("3 = x"), "column 1: expected a name to assign");
+    assert_eq!(
+        error("weight = 2; ax = weight * x"),
+        "column 1: \"weight\" is reserved for the source weight"
+    );
+    assert_eq!(error("ax = b; b = 1"), "column 6: unknown variable \"b\"");
+    assert_eq!(error("k = 1"), "expression must assign ax or ay");
+}
+
+#[test]
+fn statements() {
+    assert_eq!(ax("x; ay = 1;; "), 2.0);
+    let expression = FieldExpression::parse("r = x * x + y * y\nax = r; ay = -r").unwrap();
+    let a = expression.acceleration(&particle(), 0.0);
+    assert_eq!((a.x, a.y), (13.0, -13.0));
+}
+
+#[test]
+fn constants_become_parameters() {
+    let mut expression = FieldExpression::parse("k = 10; ax = -k * x; ay = -k * y").unwrap();
+    assert_eq!(expression.parameters(), vec![(String::from("k"), 10.0)]);
+    assert_eq!(expression.acceleration(&particle(), 0.0).x, -20.0);
+
+    expression.set_parameter("k", 2.0).unwrap();
+    assert_eq!(expression.acceleration(&particle(), 0.0).y, -6.0);
+    assert_eq!(expression.parameter_defaults(), vec![(String::from("k"), 10.0)]);
+    assert!(expression.set_parameter("c", 1.0).is_err());
+    assert!(!expression.uses_time());
+    assert!(FieldExpression::parse("ax = sin(t)").unwrap().uses_time());
+}
+
+#[test]
+fn reassigned_names_are_not_parameters() {
+    let expression = FieldExpression::parse("a = x; a = 5; ax = a").unwrap();
+    assert!(expression.parameters().is_empty());
+    assert_eq!(expression.acceleration(&particle(), 0.0).x, 5.0);
+
+    let expression = FieldExpression::parse("a = 5; a = a + x; ax = a").unwrap();
+    assert!(expression.parameters().is_empty());
+    assert_eq!(expression.acceleration(&particle(), 0.0).x, 7.0);
+
+    let expression = FieldExpression::parse("k = 1; ax = k * x; k = 3; ay = k").unwrap();
+    assert!(expression.parameters().is_empty());
+    let a = expression.acceleration(&particle(), 0.0);
+    assert_eq!((a.x, a.y), (2.0, 3.0));
+}
+
+#[test]
+fn deep_nesting_is_an_error() {
+    let parens = format!("ax = {}x{}", "(".repeat(100_000), ")".repeat(100_000));
+    let minus = format!("ax = {}x", "-".repeat(100_000));
+    let powers = format!("ax = x{}", "^x".repeat(100_000));
+    let sum = format!("ax = x{}", "+x".repeat(100_000));
+    for text in &[parens, minus, powers, sum] {
+        let error = error(text);
+        assert!(error.ends_with("expression is nested too deeply"), "{}", error);
+    }
+
+    assert_eq!(ax(&format!("{}x{}", "(".repeat(50), ")".repeat(50))), 2.0);
+    assert_eq!(ax(&format!("x{}", " + x".repeat(199))), 400.0);
+}
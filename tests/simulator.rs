@@ -0,0 +1,309 @@
+use sdl_physics::simulator::boundary::{Boundary, BoundaryMode};
+use sdl_physics::simulator::emitters::{Emitter, Sink, SpeedDistribution};
+use sdl_physics::simulator::integrator::{integrator_from_name, VelocityVerlet};
+use sdl_physics::simulator::interaction::Interaction;
+use sdl_physics::simulator::parameters::HarmonicParams;
+use sdl_physics::simulator::scene::Scene;
+use sdl_physics::simulator::util::PhysVector;
+use sdl_physics::simulator::{SimulateFunction, Simulator};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() <= 1e-5 * b.abs().max(1.0)
+}
+
+#[test]
+fn changing_the_timestep_keeps_the_elapsed_time() {
+    let mut sim = Simulator::new(0.02);
+    sim.set_function(SimulateFunction::NoForce);
+    let sim = Rc::new(RefCell::new(sim));
+    Simulator::step(sim.clone(), 50);
+    assert!(close(sim.borrow().time(), 1.0));
+
+    sim.borrow_mut().set_parameter("timestep", 0.005).unwrap();
+    assert!(close(sim.borrow().time(), 1.0));
+    Simulator::step(sim.clone(), 40);
+    assert!(close(sim.borrow().time(), 1.2));
+    assert_eq!(sim.borrow().steps(), 90);
+}
+
+#[test]
+fn scenes_keep_the_clock() {
+    let mut sim = Simulator::new(0.02);
+    sim.set_function(SimulateFunction::NoForce);
+    let sim = Rc::new(RefCell::new(sim));
+    Simulator::step(sim.clone(), 50);
+    sim.borrow_mut().set_timestep(0.01);
+    Simulator::step(sim.clone(), 10);
+
+    let text = sim.borrow().scene().to_json().unwrap();
+    let mut loaded = Simulator::new(1.0);
+    loaded.apply_scene(Scene::from_json(&text).unwrap()).unwrap();
+    assert!(close(loaded.time(), 1.1));
+    assert_eq!(loaded.steps(), 60);
+
+    // older files have no clock and take it from the step count
+    let mut value: serde_json::Value = serde_json::from_str(&text).unwrap();
+    value.as_object_mut().unwrap().remove("time");
+    let mut old = Simulator::new(1.0);
+    old.apply_scene(Scene::from_json(&value.to_string()).unwrap())
+        .unwrap();
+    assert!(close(old.time(), 0.6));
+}
+
+fn drift(sim: &Rc<RefCell<Simulator>>) -> f32 {
+    sim.borrow().diagnostics().energy_drift.unwrap()
+}
+
+/* a unit mass released from y = 1 in a harmonic well with k = 10 */
+fn oscillator(integrator: &str) -> Rc<RefCell<Simulator>> {
+    let mut sim = Simulator::new(0.01);
+    sim.set_function(SimulateFunction::Harmonic(HarmonicParams { k: 10.0 }));
+    sim.set_integrator(integrator_from_name(integrator).unwrap());
+    sim.add_particle(0.0, 1.0, 0.0, 0.0, 1.0, 0.0).unwrap();
+    Rc::new(RefCell::new(sim))
+}
+
+/* explicit Euler inflates the oscillator's energy every step */
+fn growing_oscillator() -> Rc<RefCell<Simulator>> {
+    oscillator("Explicit Euler")
+}
+
+/* the largest |drift| seen while stepping `steps` times, checked every 10 steps */
+fn worst_drift(sim: &Rc<RefCell<Simulator>>, steps: usize) -> f32 {
+    let mut worst = 0.0_f32;
+    for _ in 0..steps / 10 {
+        Simulator::step(sim.clone(), 10);
+        worst = worst.max(drift(sim).abs());
+    }
+    worst
+}
+
+#[test]
+fn symplectic_integrators_keep_the_energy_bounded() {
+    for name in &["Velocity Verlet", "Leapfrog", "Semi-Implicit Euler"] {
+        let worst = worst_drift(&oscillator(name), 10_000);
+        // the error oscillates with the orbit rather than building up
+        let bound = if *name == "Semi-Implicit Euler" { 0.05 } else { 1e-3 };
+        assert!(worst < bound, "{} drifted by {}", name, worst);
+    }
+    let euler = oscillator("Explicit Euler");
+    let early = worst_drift(&euler, 1000);
+    assert!(worst_drift(&euler, 9000) > 100.0 * early);
+}
+
+#[test]
+fn rk4_follows_the_exact_oscillator() {
+    let sim = oscillator("RK4");
+    let omega = 10.0_f32.sqrt();
+    for _ in 0..10 {
+        Simulator::step(sim.clone(), 100);
+        let t = sim.borrow().time();
+        let p = sim.borrow().particle(0).unwrap();
+        assert!((p.get_pos().y - (omega * t).cos()).abs() < 1e-4, "y at {}", t);
+        assert!((p.get_vel().y + omega * (omega * t).sin()).abs() < 1e-3, "vy at {}", t);
+    }
+    assert!(worst_drift(&sim, 10_000) < 1e-4);
+}
+
+#[test]
+fn emitters_and_sinks_keep_the_drift_reference() {
+    let alone = growing_oscillator();
+    for _ in 0..200 {
+        Simulator::step(alone.clone(), 1);
+    }
+
+    // light particles slide along y = 0 where the well does nothing, into a sink
+    let flowing = growing_oscillator();
+    let mut emitter = Emitter::point(PhysVector { x: 0.0, y: 0.0 }, 0.0, 1.0);
+    emitter.speed = SpeedDistribution::Constant(1.0);
+    emitter.spread = 0.0;
+    emitter.mass = 1e-4;
+    flowing.borrow_mut().add_emitter(emitter).unwrap();
+    flowing
+        .borrow_mut()
+        .add_sink(Sink::circle(PhysVector { x: 0.5, y: 0.0 }, 0.1));
+    for _ in 0..200 {
+        Simulator::step(flowing.clone(), 1);
+    }
+
+    assert!(flowing.borrow().sinks()[0].absorbed > 0);
+    assert!(drift(&alone) > 0.1);
+    assert!((drift(&flowing) - drift(&alone)).abs() <= 1e-3 * drift(&alone));
+}
+
+#[test]
+fn absorbed_and_emitted_energy_is_not_drift() {
+    let mut sim = Simulator::new(1.0 / 120.0);
+    sim.set_function(SimulateFunction::NoForce);
+    sim.set_integrator(Box::new(VelocityVerlet));
+    sim.set_interaction(Interaction::default_gravity());
+    sim.set_boundary(Boundary::centered(4.0, 4.0, BoundaryMode::Absorb));
+    for &(x, y) in &[(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)] {
+        sim.add_particle(x, y, 0.0, 0.0, 5.0, 0.0).unwrap();
+    }
+    let mut emitter = Emitter::point(PhysVector { x: -3.0, y: 0.0 }, 0.0, 6.0);
+    emitter.spread = 1.0;
+    sim.add_emitter(emitter).unwrap();
+    sim.add_sink(Sink::circle(PhysVector { x: -1.5, y: 0.0 }, 0.3));
+    let sim = Rc::new(RefCell::new(sim));
+
+    for _ in 0..30 {
+        Simulator::step(sim.clone(), 10);
+        assert!(drift(&sim).abs() < 1e-3, "drift {}", drift(&sim));
+    }
+    assert!(sim.borrow().sinks()[0].absorbed > 0);
+}
+
+#[test]
+fn a_loaded_scene_emits_the_same_particles() {
+    let mut sim = Simulator::new(1.0 / 120.0);
+    sim.set_function(SimulateFunction::NoForce);
+    sim.set_seed(42);
+    let mut emitter = Emitter::line(
+        PhysVector { x: -1.0, y: 0.0 },
+        PhysVector { x: 1.0, y: 0.0 },
+        3.0,
+    );
+    emitter.rate = 50.0;
+    sim.add_emitter(emitter).unwrap();
+    let sim = Rc::new(RefCell::new(sim));
+    Simulator::step(sim.clone(), 37);
+    let text = sim.borrow().scene().to_json().unwrap();
+
+    let run = || {
+        let mut loaded = Simulator::new(1.0);
+        loaded.apply_scene(Scene::from_json(&text).unwrap()).unwrap();
+        let loaded = Rc::new(RefCell::new(loaded));
+        Simulator::step(loaded.clone(), 60);
+        let particles = loaded.borrow().particle_list.borrow().clone();
+        particles
+            .iter()
+            .map(|p| (p.get_id(), p.get_pos(), p.get_vel()))
+            .map(|(id, pos, vel)| (id, [pos.x, pos.y, vel.x, vel.y]))
+            .collect::<Vec<_>>()
+    };
+    let first = run();
+    assert_eq!(first, run());
+
+    // carrying on without the save gives the same particles too
+    Simulator::step(sim.clone(), 60);
+    assert_eq!(first.len(), sim.borrow().particle_list.borrow().len());
+    for (p, (id, values)) in sim.borrow().particle_list.borrow().iter().zip(&first) {
+        let (pos, vel) = (p.get_pos(), p.get_vel());
+        assert_eq!((p.get_id(), [pos.x, pos.y, vel.x, vel.y]), (*id, *values));
+    }
+
+    // another seed gives another stream
+    let mut scene = Scene::from_json(&text).unwrap();
+    assert_eq!(scene.seed, 42);
+    scene.seed = 7;
+    let mut other = Simulator::new(1.0);
+    other.apply_scene(scene).unwrap();
+    let other = Rc::new(RefCell::new(other));
+    Simulator::step(other.clone(), 60);
+    let last = other.borrow().particle_list.borrow().last().unwrap().get_pos();
+    let (x, y) = (first.last().unwrap().1[0], first.last().unwrap().1[1]);
+    assert!(last.x != x || last.y != y);
+}
+
+#[test]
+fn frozen_particles_are_immovable_in_collisions() {
+    let mut sim = Simulator::new(0.01);
+    sim.set_function(SimulateFunction::NoForce);
+    sim.collide_particles = true;
+    sim.add_particle(0.0, 0.0, 2.0, 0.0, 1.0, 0.0).unwrap();
+    sim.add_particle(1.0, 0.0, 0.0, 0.0, 1.0, 0.0).unwrap();
+    sim.set_frozen(&[1], true);
+    let sim = Rc::new(RefCell::new(sim));
+    Simulator::step(sim.clone(), 50);
+
+    let sim = sim.borrow();
+    let (moving, wall) = (sim.particle(0).unwrap(), sim.particle(1).unwrap());
+    // bounced straight back at full speed
+    assert!(close(moving.get_vel().x, -2.0));
+    assert!(moving.get_pos().x <= 1.0 - 2.0 * moving.get_radius());
+    assert_eq!((wall.get_pos().x, wall.get_pos().y), (1.0, 0.0));
+}
+
+#[test]
+fn emitter_settings_are_checked() {
+    let mut sim = Simulator::new(0.01);
+    sim.add_emitter(Emitter::point(PhysVector { x: 0.0, y: 0.0 }, 0.0, 1.0))
+        .unwrap();
+    let text = sim.scene().to_json().unwrap();
+    let bad = [
+        ("rate", serde_json::json!(1e30), "rate must be positive and at most"),
+        ("rate", serde_json::json!(0.0), "rate must be positive and at most"),
+        ("spread", serde_json::json!(-1.0), "spread must be zero or more"),
+        ("lifetime", serde_json::json!(0.0), "lifetime must be positive"),
+    ];
+    for (field, value, message) in bad.iter() {
+        let mut scene: serde_json::Value = serde_json::from_str(&text).unwrap();
+        scene["emitters"][0][*field] = value.clone();
+        let scene = Scene::from_json(&scene.to_string()).unwrap();
+        let error = Simulator::new(0.01).apply_scene(scene).err().unwrap();
+        assert!(error.contains(message), "{}", error);
+    }
+    // serde_json can't hold an infinity, set it directly
+    let mut emitter = Emitter::point(PhysVector { x: 0.0, y: 0.0 }, 0.0, 1.0);
+    emitter.rate = f32::INFINITY;
+    assert!(sim.add_emitter(emitter.clone()).is_err());
+    assert!(sim.set_emitters(vec![emitter]).is_err());
+}
+
+#[test]
+fn emission_per_step_is_capped() {
+    let mut sim = Simulator::new(0.01);
+    sim.set_function(SimulateFunction::NoForce);
+    let mut emitter = Emitter::point(PhysVector { x: 0.0, y: 0.0 }, 0.0, 1.0);
+    emitter.rate = 1e6;
+    sim.add_emitter(emitter).unwrap();
+    let sim = Rc::new(RefCell::new(sim));
+    Simulator::step(sim.clone(), 2);
+    assert_eq!(sim.borrow().particle_list.borrow().len(), 2000);
+}
+
+#[test]
+fn scenes_with_bad_boundaries_or_constants_are_rejected() {
+    let mut sim = Simulator::new(0.01);
+    sim.set_function(SimulateFunction::Harmonic(HarmonicParams { k: 10.0 }));
+    sim.set_boundary(Boundary::centered(5.0, 5.0, BoundaryMode::Wrap));
+    let text = sim.scene().to_json().unwrap();
+    let load = |pointer: &str, value: serde_json::Value| {
+        let mut scene: serde_json::Value = serde_json::from_str(&text).unwrap();
+        *scene.pointer_mut(pointer).unwrap() = value;
+        let scene = Scene::from_json(&scene.to_string()).unwrap();
+        Simulator::new(0.01).apply_scene(scene)
+    };
+
+    assert!(load("/boundary/x_max", serde_json::json!(4.0)).is_ok());
+    let error = load("/boundary/x_max", serde_json::json!(-5.0)).err().unwrap();
+    assert!(error.contains("boundary x range"), "{}", error);
+    let error = load("/boundary/y_min", serde_json::json!(5.0)).err().unwrap();
+    assert!(error.contains("boundary y range"), "{}", error);
+    let reflect = serde_json::json!({ "Reflect": { "restitution": 2.0 } });
+    assert!(load("/boundary/mode", reflect).is_err());
+
+    let error = load("/forces/0/weight", serde_json::json!(50.0)).err().unwrap();
+    assert!(error.contains("Harmonic.weight"), "{}", error);
+    let error = load("/forces/0/function/Harmonic/k", serde_json::json!(1e9)).err().unwrap();
+    assert!(error.contains("Harmonic.k"), "{}", error);
+    assert!(load("/restitution", serde_json::json!(-1.0)).is_err());
+}
+
+#[test]
+fn a_grabbed_particle_absorbed_by_the_walls_is_let_go() {
+    let mut sim = Simulator::new(0.01);
+    sim.set_function(SimulateFunction::NoForce);
+    sim.set_boundary(Boundary::centered(5.0, 5.0, BoundaryMode::Absorb));
+    sim.add_particle(4.9, 0.0, 50.0, 0.0, 1.0, 0.0).unwrap();
+    assert!(sim.grab(0, 4.9, 0.0));
+    let sim = Rc::new(RefCell::new(sim));
+    sim.borrow_mut().move_grab(20.0, 0.0);
+    Simulator::step(sim.clone(), 5);
+
+    assert!(sim.borrow().particle(0).is_none());
+    assert!(sim.borrow().joint().is_none());
+}
@@ -0,0 +1,128 @@
+use sdl_physics::simulator::trajectory::{
+    read_binary, read_trajectory, Frame, TrajectoryFormat, TrajectoryRecorder,
+};
+use sdl_physics::simulator::{SimulateFunction, Simulator};
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("sdl_physics_{}_{}", std::process::id(), name))
+}
+
+// (id, [x, y, vx, vy]) for each particle
+type Particles = Vec<(u32, [f32; 4])>;
+
+fn snapshot(sim: &Simulator) -> Particles {
+    sim.particle_list
+        .borrow()
+        .iter()
+        .map(|p| {
+            let (pos, vel) = (p.get_pos(), p.get_vel());
+            (p.get_id(), [pos.x, pos.y, vel.x, vel.y])
+        })
+        .collect()
+}
+
+fn frame_values(frame: &Frame) -> Particles {
+    frame
+        .samples
+        .iter()
+        .map(|s| (s.id, [s.pos.x, s.pos.y, s.vel.x, s.vel.y]))
+        .collect()
+}
+
+/* writes three frames of a small system and a final empty one, returning what each
+frame should hold */
+fn write_frames(path: &Path, format: TrajectoryFormat) -> Vec<(f32, Particles)> {
+    let mut sim = Simulator::new(1.0 / 120.0);
+    sim.set_function(SimulateFunction::Harmonic(Default::default()));
+    sim.add_particle(1.0, 0.0, 0.0, 2.0, 1.0, 1.0).unwrap();
+    sim.add_particle(-3.5, 2.25, 0.5, -1.0, 2.0, -1.0).unwrap();
+    sim.add_particle(0.1, -0.2, 0.0, 0.0, 1.0, 1.0).unwrap();
+    let sim = Rc::new(RefCell::new(sim));
+
+    let mut recorder = TrajectoryRecorder::create(path.to_str().unwrap(), format, 1).unwrap();
+    let mut expected = Vec::new();
+    for _ in 0..3 {
+        recorder.write_frame(&sim.borrow()).unwrap();
+        expected.push((sim.borrow().time(), snapshot(&sim.borrow())));
+        Simulator::step(sim.clone(), 7);
+    }
+    sim.borrow_mut().clear();
+    recorder.write_frame(&sim.borrow()).unwrap();
+    expected.push((sim.borrow().time(), Vec::new()));
+    assert_eq!(recorder.frames_written(), 4);
+    recorder.finish().unwrap();
+    expected
+}
+
+fn check_round_trip(name: &str, format: TrajectoryFormat) {
+    let path = temp_path(name);
+    let expected = write_frames(&path, format);
+    let frames = read_trajectory(path.to_str().unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(frames.len(), expected.len());
+    for (frame, (time, particles)) in frames.iter().zip(expected.iter()) {
+        assert_eq!(frame.time, *time);
+        assert_eq!(frame_values(frame), *particles);
+    }
+}
+
+#[test]
+fn csv_round_trip() {
+    check_round_trip("round_trip.csv", TrajectoryFormat::Csv);
+}
+
+#[test]
+fn binary_round_trip() {
+    check_round_trip("round_trip.sptr", TrajectoryFormat::Binary);
+}
+
+#[test]
+fn truncated_binary_is_an_error() {
+    let path = temp_path("truncated.sptr");
+    write_frames(&path, TrajectoryFormat::Binary);
+    let bytes = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(read_binary(&bytes).is_ok());
+    // cut into the header of the empty last frame, then into the particle before it
+    for cut in &[5, 8 + 9] {
+        let error = read_binary(&bytes[..bytes.len() - cut]).err().unwrap();
+        assert!(error.contains("unexpected end"), "{}", error);
+    }
+}
+
+#[test]
+fn bad_magic_is_an_error() {
+    let error = read_binary(b"SPTX\x01\x00\x00\x00").err().unwrap();
+    assert!(error.contains("not a binary trajectory"), "{}", error);
+    assert!(read_binary(b"SP").is_err());
+}
+
+#[test]
+fn record_counts_from_the_first_step() {
+    let mut sim = Simulator::new(0.5);
+    sim.set_function(SimulateFunction::NoForce);
+    sim.add_particle(0.0, 0.0, 1.0, 0.0, 1.0, 1.0).unwrap();
+    let sim = Rc::new(RefCell::new(sim));
+    // as if a scene saved at step 5 had been loaded
+    Simulator::step(sim.clone(), 5);
+
+    let path = temp_path("offset.csv");
+    let mut recorder =
+        TrajectoryRecorder::create(path.to_str().unwrap(), TrajectoryFormat::Csv, 3).unwrap();
+    for _ in 0..7 {
+        recorder.record(&sim.borrow()).unwrap();
+        Simulator::step(sim.clone(), 1);
+    }
+    recorder.finish().unwrap();
+    let frames = read_trajectory(path.to_str().unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let times: Vec<f32> = frames.iter().map(|f| f.time).collect();
+    assert_eq!(times, vec![2.5, 4.0, 5.5]);
+}